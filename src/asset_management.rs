@@ -1,9 +1,11 @@
 use std::ffi::CString;
 use std::{env, ffi::OsString};
 use std::{fs::File, io::Read};
+pub mod bounds;
 pub mod cube;
 pub mod mesh;
 pub mod model;
+pub mod picking;
 
 use crate::cstr;
 
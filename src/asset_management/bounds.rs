@@ -0,0 +1,60 @@
+use nalgebra_glm as glm;
+
+/// Axis-aligned box and enclosing sphere around a set of points.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoundingVolume {
+    pub min: glm::Vec3,
+    pub max: glm::Vec3,
+    pub center: glm::Vec3,
+    pub radius: f32,
+}
+
+impl BoundingVolume {
+    pub fn from_min_max(min: glm::Vec3, max: glm::Vec3) -> Self {
+        Self {
+            min,
+            max,
+            center: (min + max) / 2f32,
+            radius: glm::distance(&min, &max) / 2f32,
+        }
+    }
+
+    /// `None` when there are no points.
+    pub fn from_points(points: impl IntoIterator<Item = glm::Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| {
+            (glm::min2(&min, &p), glm::max2(&max, &p))
+        });
+        Some(Self::from_min_max(min, max))
+    }
+
+    pub fn corners(&self) -> [glm::Vec3; 8] {
+        std::array::from_fn(|i| {
+            glm::vec3(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    /// The box around the transformed corners, and the sphere moved and scaled by `matrix`.
+    pub fn transformed(&self, matrix: &glm::Mat4) -> Self {
+        let corners = self.corners().map(|c| (matrix * c.push(1f32)).xyz());
+        let mut bounds = Self::from_points(corners).unwrap();
+        let scale = (0..3)
+            .map(|i| matrix.fixed_view::<3, 1>(0, i).norm())
+            .fold(0f32, f32::max);
+        bounds.center = (matrix * self.center.push(1f32)).xyz();
+        bounds.radius = self.radius * scale;
+        bounds
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_min_max(
+            glm::min2(&self.min, &other.min),
+            glm::max2(&self.max, &other.max),
+        )
+    }
+}
@@ -1,7 +1,10 @@
 use gl;
 use nalgebra_glm as glm;
 
-use crate::{shader::Shader, texture::Texture};
+use super::bounds::BoundingVolume;
+use crate::engine::stats;
+use crate::shader::{Shader, ShaderFeatures};
+use crate::texture::Texture;
 
 #[repr(C)]
 #[derive(Clone)]
@@ -31,17 +34,25 @@ pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
     pub textures: Vec<Texture>,
+    /// Around the vertices in model space.
+    pub bounds: BoundingVolume,
     VAO: u32,
     VBO: u32,
     EBO: u32,
 }
 
 impl Mesh {
-    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, textures: Vec<Texture>) -> Self {
+    pub fn new(
+        vertices: Vec<Vertex>,
+        indices: Vec<u32>,
+        textures: Vec<Texture>,
+        bounds: BoundingVolume,
+    ) -> Self {
         let mut m = Mesh {
             vertices,
             indices,
             textures,
+            bounds,
             VAO: 0,
             VBO: 0,
             EBO: 0,
@@ -115,6 +126,19 @@ impl Mesh {
             BindVertexArray(0);
         }
     }
+    /// Features this mesh's textures can provide.
+    pub fn features(&self) -> ShaderFeatures {
+        let mut features = ShaderFeatures::NONE;
+        for texture in &self.textures {
+            match texture.type_s.as_str() {
+                "texture_normal" => features |= ShaderFeatures::NORMAL_MAPPING,
+                "texture_height" => features |= ShaderFeatures::HEIGHT_MAPPING,
+                _ => {}
+            }
+        }
+        features
+    }
+
     pub fn draw(&self, shader: &Shader) {
         unsafe {
             use gl::*;
@@ -144,11 +168,13 @@ impl Mesh {
                 } else {
                     panic!("Could not get texture!");
                 }
-                shader.setInt(
-                    format!("material.{}{}", name, number.to_str().unwrap()).as_str(),
-                    i as i32,
-                );
+                // Variants without a feature don't declare its sampler.
+                let uniform = format!("material.{}{}", name, number.to_str().unwrap());
+                if shader.has_uniform(&uniform) {
+                    shader.setInt(&uniform, i as i32);
+                }
                 BindTexture(TEXTURE_2D, self.textures[i].ID);
+                stats::count_texture_bind();
             }
 
             BindVertexArray(self.VAO);
@@ -158,6 +184,7 @@ impl Mesh {
                 UNSIGNED_INT,
                 0 as *const _,
             );
+            stats::count_draw(self.indices.len());
             BindVertexArray(0);
             ActiveTexture(TEXTURE0);
         }
@@ -3,11 +3,15 @@ extern crate russimp;
 
 use std::time::Instant;
 
+use crate::shader::{ShaderFeatures, ShaderVariants};
+use crate::texture::Texture;
 use crate::texture::TextureConstructor;
-use crate::{shader::Shader, texture::Texture};
 
+use super::bounds::BoundingVolume;
 use super::get_asset_path;
 use super::mesh::{Mesh, Vertex};
+use crate::camera::Frustum;
+use crate::engine::stats;
 use log::debug;
 use russimp::material::Material as AIMaterial;
 use russimp::material::TextureType as AITextureType;
@@ -24,6 +28,8 @@ pub struct Model {
     pub position: glm::Vec3,
     pub rotation: (f32, glm::Vec3),
     pub height_mapping_enabled: bool,
+    previous_position: glm::Vec3,
+    previous_rotation: (f32, glm::Vec3),
 }
 
 impl Model {
@@ -35,44 +41,101 @@ impl Model {
         rotation: Option<(f32, glm::Vec3)>,
         height_mapping_enabled: Option<bool>,
     ) -> Self {
+        let position = match position {
+            Some(n) => n,
+            None => glm::Vec3::identity(),
+        };
+        let rotation = match rotation {
+            Some(n) => n,
+            None => (0f32, glm::vec3(0f32, -1.0, 0.0)),
+        };
         let mut result = Self {
             textures_loaded: Vec::new(),
             meshes: Vec::new(),
             directory: String::new(),
-            position: match position {
-                Some(n) => n,
-                None => glm::Vec3::identity(),
-            },
-            rotation: match rotation {
-                Some(n) => n,
-                None => (0f32, glm::vec3(0f32, -1.0, 0.0)),
-            },
+            position,
+            rotation,
             height_mapping_enabled: match height_mapping_enabled {
                 Some(n) => n,
                 None => false,
             },
+            previous_position: position,
+            previous_rotation: rotation,
         };
         result.load_model(path, flags);
         result
     }
-    pub fn draw(&self, shader: &Shader) {
-        unsafe {
-            shader.setBool("heightMappingEnabled", self.height_mapping_enabled);
+    /// Remembers the current transform as the previous tick's, call before advancing the simulation.
+    pub fn store_previous(&mut self) {
+        self.previous_position = self.position;
+        self.previous_rotation = self.rotation;
+    }
+
+    /// Model matrix blended between the previous and the current transform.
+    pub fn get_model_matrix(&self, alpha: f32) -> glm::Mat4 {
+        let position = glm::lerp(&self.previous_position, &self.position, alpha);
+        let rotation = glm::quat_slerp(
+            &glm::quat_angle_axis(self.previous_rotation.0, &self.previous_rotation.1),
+            &glm::quat_angle_axis(self.rotation.0, &self.rotation.1),
+            alpha,
+        );
+        glm::translate(&glm::Mat4::identity(), &position) * glm::quat_to_mat4(&rotation)
+    }
+
+    /// World space bounds of every mesh at the current transform, `None` when empty.
+    pub fn bounds(&self) -> Option<BoundingVolume> {
+        let model = self.get_model_matrix(1f32);
+        self.meshes
+            .iter()
+            .map(|mesh| mesh.bounds.transformed(&model))
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Shader features `mesh` is drawn with.
+    pub fn mesh_features(&self, mesh: &Mesh) -> ShaderFeatures {
+        let mut features = mesh.features();
+        if !self.height_mapping_enabled {
+            features.set(ShaderFeatures::HEIGHT_MAPPING, false);
+        }
+        features
+    }
+
+    /// Every combination of features the meshes need, for [`ShaderVariants::prepare`].
+    pub fn features(&self) -> Vec<ShaderFeatures> {
+        let mut all = Vec::new();
+        for mesh in &self.meshes {
+            let features = self.mesh_features(mesh);
+            if !all.contains(&features) {
+                all.push(features);
+            }
         }
+        all
+    }
+
+    /// Draws every mesh that intersects `frustum`, or all of them without one, each with the
+    /// shader variant for its textures. Meshes whose variant wasn't prepared are skipped.
+    /// Returns how many were culled.
+    pub fn draw(&self, shaders: &ShaderVariants, alpha: f32, frustum: Option<&Frustum>) -> usize {
+        let model = self.get_model_matrix(alpha);
+        let mut culled = 0;
         for mesh in self.meshes.iter() {
+            if let Some(frustum) = frustum
+                && !frustum.intersects(&mesh.bounds.transformed(&model))
+            {
+                culled += 1;
+                continue;
+            }
+            let Some(shader) = shaders.get(self.mesh_features(mesh)) else {
+                continue;
+            };
+            shader.activate();
             unsafe {
-                shader.setMat4(
-                    "model",
-                    glm::rotate(
-                        &glm::translate(&glm::Mat4::identity(), &self.position),
-                        self.rotation.0,
-                        &self.rotation.1,
-                    ),
-                    gl::FALSE,
-                );
+                shader.setMat4("model", model, gl::FALSE);
             }
             mesh.draw(shader);
         }
+        stats::count_culled(culled);
+        culled
     }
 
     #[inline(always)]
@@ -223,7 +286,9 @@ impl Model {
             textures.push(it.clone());
         });
 
-        Mesh::new(vertices, indices, textures)
+        let bounds =
+            BoundingVolume::from_points(vertices.iter().map(|v| v.position)).unwrap_or_default();
+        Mesh::new(vertices, indices, textures, bounds)
     }
     #[inline(always)]
     fn load_material_textures(
@@ -0,0 +1,81 @@
+use nalgebra_glm as glm;
+
+use super::mesh::Mesh;
+use super::model::Model;
+use crate::camera::Ray;
+
+/// Where a ray first meets a model.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Index into the models that were searched.
+    pub model: usize,
+    /// Index into the model's `meshes`.
+    pub mesh: usize,
+    /// Index of the triangle in the mesh, its corners are `indices[3 * triangle..][..3]`.
+    pub triangle: usize,
+    /// World space distance from the ray origin.
+    pub distance: f32,
+    /// Weights of the triangle's three corners at the hit point.
+    pub barycentric: glm::Vec3,
+    /// World space hit point.
+    pub point: glm::Vec3,
+}
+
+impl Mesh {
+    /// Nearest triangle hit by a model space `ray`, as `(triangle, t, u, v)` where `u` and `v`
+    /// weight the second and third corners.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<(usize, f32, f32, f32)> {
+        ray.intersect_sphere(&self.bounds.center, self.bounds.radius)?;
+        let mut nearest: Option<(usize, f32, f32, f32)> = None;
+        for (triangle, corners) in self.indices.chunks_exact(3).enumerate() {
+            let [a, b, c] = [0, 1, 2].map(|i| &self.vertices[corners[i] as usize].position);
+            if let Some((t, u, v)) = ray.intersect_triangle(a, b, c)
+                && nearest.is_none_or(|(_, nearest_t, _, _)| t < nearest_t)
+            {
+                nearest = Some((triangle, t, u, v));
+            }
+        }
+        nearest
+    }
+}
+
+impl Model {
+    /// Nearest hit of a world space `ray` on any mesh, at the current transform. `RayHit::model`
+    /// is left as 0.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<RayHit> {
+        let model = self.get_model_matrix(1f32);
+        let local = ray.transformed(&glm::inverse(&model));
+        let mut nearest: Option<RayHit> = None;
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            let Some((triangle, t, u, v)) = mesh.intersect_ray(&local) else {
+                continue;
+            };
+            let point = (model * local.at(t).push(1f32)).xyz();
+            let distance = glm::distance(&ray.origin, &point);
+            if nearest.is_none_or(|hit| distance < hit.distance) {
+                nearest = Some(RayHit {
+                    model: 0,
+                    mesh: i,
+                    triangle,
+                    distance,
+                    barycentric: glm::vec3(1f32 - u - v, u, v),
+                    point,
+                });
+            }
+        }
+        nearest
+    }
+}
+
+/// Nearest hit of `ray` on any of `models`.
+pub fn pick(models: &[Model], ray: &Ray) -> Option<RayHit> {
+    models
+        .iter()
+        .enumerate()
+        .filter_map(|(i, model)| {
+            model
+                .intersect_ray(ray)
+                .map(|hit| RayHit { model: i, ..hit })
+        })
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
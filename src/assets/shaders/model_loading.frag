@@ -8,35 +8,7 @@ struct Material {
     sampler2D texture_normal1;
 };
 
-struct DirectionalLight {
-    vec3 direction;
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-};
-
-struct PointLight {
-    vec3 position;
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-    float constant;
-    float linear;
-    float quadratic;
-};
-
-struct SpotLight {
-    vec3 position;
-    vec3 direction;
-    float cutOff;
-    float outerCutOff;
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-    float constant;
-    float linear;
-    float quadratic;
-};
+#include "lighting.glsl"
 
 in VS_OUT {
     vec3 FragPos;
@@ -47,36 +19,51 @@ in VS_OUT {
     vec3 TangentFragPos;
 } fs_in;
 
+// Features are defined per variant by ShaderVariants: HEIGHT_MAPPING and NORMAL_MAPPING.
+
+// Defaults for when the viewer does not define these.
+#ifndef NR_POINT_LIGHTS
 #define NR_POINT_LIGHTS 4
+#endif
+#ifndef MATERIAL_SHININESS
 #define MATERIAL_SHININESS 32.0
+#endif
+#ifndef height_scale
 #define height_scale 0.1
+#endif
 
 uniform vec3 viewPos;
 uniform Material material;
 uniform PointLight pointLights[NR_POINT_LIGHTS];
 uniform DirectionalLight dirLight;
 uniform SpotLight spotLight;
-uniform bool heightMappingEnabled;
 
 vec3 CalcDirLight(DirectionalLight light, vec3 normal, vec3 viewDir, vec2 texCoords);
 vec3 CalcPointLight(PointLight light, vec3 normal, vec3 fragPos, vec3 viewDir, vec2 texCoords);
 vec3 CalcSpotLight(SpotLight light, vec3 normal, vec3 fragPos, vec3 viewDir, vec2 texCoords);
 
+#ifdef HEIGHT_MAPPING
 vec2 ParallaxMapping(vec2 texCoords, vec3 viewDir);
+#endif
 
 void main()
 {
     vec3 viewDir = normalize(fs_in.TangentViewPos - fs_in.TangentFragPos);
     vec2 texCoords = fs_in.TexCoords;
-    if (heightMappingEnabled == true) {
-        texCoords = ParallaxMapping(fs_in.TexCoords, viewDir);
-        if (texCoords.x > 1.0 || texCoords.y > 1.0 || texCoords.x < 0.0 || texCoords.y < 0.0) {
-            discard;
-        }
+#ifdef HEIGHT_MAPPING
+    texCoords = ParallaxMapping(fs_in.TexCoords, viewDir);
+    if (texCoords.x > 1.0 || texCoords.y > 1.0 || texCoords.x < 0.0 || texCoords.y < 0.0) {
+        discard;
     }
+#endif
 
+#ifdef NORMAL_MAPPING
     vec3 normal = texture(material.texture_normal1, texCoords).rgb;
     normal = normalize(normal * 2.0 - 1.0);
+#else
+    // Lighting is done in tangent space, where the surface normal points along z.
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+#endif
 
     vec3 result = CalcDirLight(dirLight, normal, viewDir, texCoords);
 
@@ -87,6 +74,7 @@ void main()
     FragColor = vec4(result, 1.0);
 }
 
+#ifdef HEIGHT_MAPPING
 vec2 ParallaxMapping(vec2 texCoords, vec3 viewDir) {
     const float minLayers = 8.0;
     const float maxLayers = 32.0;
@@ -121,6 +109,7 @@ vec2 ParallaxMapping(vec2 texCoords, vec3 viewDir) {
 
     return finalTexCoords;
 }
+#endif
 
 vec3 color(vec2 texCoords) {
     return texture(material.texture_diffuse1, texCoords).rgb;
@@ -138,7 +127,7 @@ vec3 CalcDirLight(DirectionalLight light, vec3 normal, vec3 viewDir, vec2 texCoo
 
     vec3 reflectDir = reflect(-lightDir, normal);
     vec3 halfwayDir = normalize(lightDir + viewDir);
-    float spec = pow(max(dot(normal, halfwayDir), 0.0), 32.0);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), MATERIAL_SHININESS);
 
     vec3 specular = light.specular * spec * texture(material.texture_specular1, texCoords).rgb;
 
@@ -159,7 +148,7 @@ vec3 CalcPointLight(PointLight light, vec3 normal, vec3 fragPos, vec3 viewDir, v
 
     vec3 reflectDir = reflect(-lightDir, normal);
     vec3 halfwayDir = normalize(lightDir + viewDir);
-    float spec = pow(max(dot(normal, halfwayDir), 0.0), 32.0);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), MATERIAL_SHININESS);
 
     vec3 specular = light.specular * spec * texture(material.texture_specular1, texCoords).rgb;
 
@@ -190,7 +179,7 @@ vec3 CalcSpotLight(SpotLight light, vec3 normal, vec3 fragPos, vec3 viewDir, vec
 
     vec3 reflectDir = reflect(-lightDir, normal);
     vec3 halfwayDir = normalize(lightDir + viewDir);
-    float spec = pow(max(dot(normal, halfwayDir), 0.0), 32.0);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), MATERIAL_SHININESS);
 
     vec3 specular = light.specular * spec * texture(material.texture_specular1, texCoords).rgb;
 
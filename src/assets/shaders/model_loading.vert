@@ -14,18 +14,8 @@ out VS_OUT {
     vec3 TangentFragPos;
 } vs_out;
 
-struct SpotLight {
-    vec3 position;
-    vec3 direction;
-    float cutOff;
-    float outerCutOff;
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-    float constant;
-    float linear;
-    float quadratic;
-};
+#include "lighting.glsl"
+
 uniform SpotLight spotLight;
 
 uniform mat4 model;
@@ -1,7 +1,13 @@
 #![allow(dead_code)]
 use nalgebra_glm as glm;
+use serde::Deserialize;
 
-use crate::as_mut_expect;
+pub mod frustum;
+pub mod path;
+pub mod ray;
+pub use frustum::Frustum;
+pub use path::{CameraPath, CameraPathPlayer, Keyframe};
+pub use ray::Ray;
 
 const MAX_ZOOM: f32 = 89f32;
 const MIN_ZOOM: f32 = 1f32;
@@ -11,12 +17,32 @@ const PITCH: f32 = 0f32;
 const SPEED: f32 = 2.5f32;
 const SENSITIVITY: f32 = 0.1f32;
 const ZOOM: f32 = 45f32;
+/// Degrees per second at full roll input.
+const ROLL_SPEED: f32 = 90f32;
+
+const ORBIT_DISTANCE: f32 = 5f32;
+const MIN_DISTANCE: f32 = 0.05f32;
+/// Distance kept per scroll step when dollying.
+const DOLLY_STEP: f32 = 0.9f32;
+/// Pan per pixel of mouse movement, relative to the orbit distance.
+const PAN_SPEED: f32 = 0.002f32;
+/// Seconds taken to fly to a new orbit target.
+const TRANSITION_TIME: f32 = 0.35f32;
+
+const ORTHO_HEIGHT: f32 = 10f32;
+const MIN_ORTHO_HEIGHT: f32 = 0.01f32;
+/// Seconds taken to blend between perspective and orthographic projection.
+const PROJECTION_TIME: f32 = 0.4f32;
 
 pub enum CameraMovement {
     FORWARD,
     BACKWARD,
     LEFT,
     RIGHT,
+    UP,
+    DOWN,
+    ROLL_LEFT,
+    ROLL_RIGHT,
 }
 
 impl CameraMovement {
@@ -26,9 +52,120 @@ impl CameraMovement {
             CameraMovement::BACKWARD => 1,
             CameraMovement::LEFT => 2,
             CameraMovement::RIGHT => 3,
+            CameraMovement::UP => 4,
+            CameraMovement::DOWN => 5,
+            CameraMovement::ROLL_LEFT => 6,
+            CameraMovement::ROLL_RIGHT => 7,
+        }
+    }
+}
+/// How mouse and movement input drive the camera.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Yaw/pitch look and free movement, scrolling changes the FOV.
+    #[default]
+    FreeFly,
+    /// Rotates around `target`, movement pans it and scrolling dollies towards it.
+    Orbit,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Projection {
+    /// Vertical field of view of `zoom` degrees.
+    #[default]
+    Perspective,
+    /// Parallel projection showing `ortho_height` world units vertically.
+    Orthographic,
+}
+
+/// Depth range the projection matrices map to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthMode {
+    /// OpenGL's default -1..1 clip range, near at depth 0 and far at 1.
+    #[default]
+    Standard,
+    /// 0..1 clip range (`glClipControl`) with near at depth 1 and far at 0, for use with a
+    /// float depth buffer and a `GREATER` depth test.
+    ReverseZ,
+}
+
+/// Axis-aligned inspection views, looking at the orbit target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewPreset {
+    /// Looking down -Y.
+    Top,
+    /// Looking down -Z.
+    Front,
+    /// Looking down -X.
+    Side,
+}
+
+impl ViewPreset {
+    /// Yaw and pitch in degrees.
+    fn angles(&self) -> (f32, f32) {
+        match self {
+            ViewPreset::Top => (-90f32, -89f32),
+            ViewPreset::Front => (-90f32, 0f32),
+            ViewPreset::Side => (180f32, 0f32),
         }
     }
 }
+
+/// How movement and mouse-look respond to input. All rates are per second, so the feel is the
+/// same at any tick rate.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct CameraMotion {
+    /// Accelerate towards the input direction and coast to a stop instead of moving at a fixed
+    /// speed.
+    pub inertia: bool,
+    /// Units per second squared the velocity changes by while there is movement input.
+    pub acceleration: f32,
+    /// Exponential decay rate of the velocity once input stops; higher stops sooner.
+    pub damping: f32,
+    /// Time constant in seconds over which mouse-look catches up with the input, 0 to disable.
+    pub look_smoothing: f32,
+    pub sprint_multiplier: f32,
+    pub slow_multiplier: f32,
+}
+
+impl Default for CameraMotion {
+    fn default() -> Self {
+        Self {
+            inertia: false,
+            acceleration: 20f32,
+            damping: 8f32,
+            look_smoothing: 0f32,
+            sprint_multiplier: 3f32,
+            slow_multiplier: 0.25f32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpeedMode {
+    #[default]
+    Normal,
+    Sprint,
+    Slow,
+}
+
+/// Blend from where the camera was to its current pose.
+#[derive(Clone, Copy, Debug)]
+struct Transition {
+    from_position: glm::Vec3,
+    from_orientation: glm::Quat,
+    elapsed: f32,
+}
+
+/// Rotation taking the camera's local axes (right +X, up +Y, looking down -Z) to `front` and `up`.
+fn basis_to_orientation(front: &glm::Vec3, up: &glm::Vec3) -> glm::Quat {
+    let front = front.normalize();
+    let right = glm::cross(&front, up).normalize();
+    let up = glm::cross(&right, &front);
+    glm::mat3_to_quat(&glm::Mat3::from_columns(&[right, up, -front]))
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Camera {
     position: glm::Vec3,
@@ -38,13 +175,34 @@ pub struct Camera {
     world_up: glm::Vec3,
     yaw: f32,
     pitch: f32,
+    /// Used instead of `yaw` and `pitch` when `free_rotation` is set, kept in sync otherwise.
+    orientation: glm::Quat,
+    /// Flight-style rotation about the camera's own axes, with roll and no pitch limit.
+    free_rotation: bool,
     pub mouse_sensitivity: f32,
     pub move_speed: f32,
+    pub motion: CameraMotion,
+    speed_mode: SpeedMode,
+    velocity: glm::Vec3,
+    /// Mouse-look offsets not applied yet while smoothing.
+    pending_look: glm::Vec2,
     pub zoom: f32,
     constrain_pitch: bool,
     aspect_ratio: f32,
     near: f32,
     far: f32,
+    mode: CameraMode,
+    target: glm::Vec3,
+    distance: f32,
+    transition: Option<Transition>,
+    projection: Projection,
+    pub ortho_height: f32,
+    depth_mode: DepthMode,
+    /// Perspective projection without a far plane; orthographic views still use `far`.
+    infinite_far: bool,
+    /// 0 is fully perspective and 1 fully orthographic, moves towards `projection` over
+    /// [`PROJECTION_TIME`].
+    projection_blend: f32,
 }
 
 impl Camera {
@@ -52,19 +210,169 @@ impl Camera {
         glm::look_at(&self.position, &(self.position + self.front), &self.up)
     }
 
+    /// World space ray through the point `x`, `y` of a `width` x `height` viewport, origin top
+    /// left as cursor positions are. Starts on the near plane.
+    pub fn screen_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Ray {
+        let ndc_x = 2f32 * x / width - 1f32;
+        let ndc_y = 1f32 - 2f32 * y / height;
+        let inverse = glm::inverse(&(self.get_projection_matrix() * self.get_view_matrix()));
+        let unproject = |z: f32| {
+            let point = inverse * glm::vec4(ndc_x, ndc_y, z, 1f32);
+            point.xyz() / point.w
+        };
+        // A point halfway into the depth range, since the far plane may be at infinity.
+        let (near_depth, middle_depth) = match self.depth_mode {
+            DepthMode::Standard => (-1f32, 0f32),
+            DepthMode::ReverseZ => (1f32, 0.5f32),
+        };
+        let near = unproject(near_depth);
+        let middle = unproject(middle_depth);
+        Ray::new(near, middle - near)
+    }
+
+    /// Clip planes of the current view and projection, for culling.
+    pub fn get_frustum(&self) -> Frustum {
+        Frustum::from_matrix(&(self.get_projection_matrix() * self.get_view_matrix()))
+    }
+
+    /// World space corners of the view volume, near plane first, then the far plane, each
+    /// counter-clockwise from bottom left. Infinite far planes are cut off at `far`.
+    pub fn frustum_corners(&self) -> [glm::Vec3; 8] {
+        let t = self.projection_blend.clamp(0f32, 1f32);
+        let t = t * t * (3f32 - 2f32 * t);
+        let half_extents = |distance: f32| {
+            let perspective = (self.zoom.to_radians() / 2f32).tan() * distance;
+            let half_height = perspective + (self.ortho_height / 2f32 - perspective) * t;
+            (half_height * self.aspect_ratio, half_height)
+        };
+        let mut corners = [glm::Vec3::zeros(); 8];
+        for (i, distance) in [self.near, self.far].into_iter().enumerate() {
+            let (w, h) = half_extents(distance);
+            let center = self.position + self.front * distance;
+            for (j, (x, y)) in [(-1f32, -1f32), (1f32, -1f32), (1f32, 1f32), (-1f32, 1f32)]
+                .into_iter()
+                .enumerate()
+            {
+                corners[i * 4 + j] = center + self.right * (x * w) + self.up * (y * h);
+            }
+        }
+        corners
+    }
+
+    /// Perspective, orthographic, or an element-wise blend of both while switching.
     pub fn get_projection_matrix(&self) -> glm::Mat4 {
-        glm::perspective(
-            self.aspect_ratio,
-            self.zoom.to_radians(),
-            self.near,
-            self.far,
-        )
+        let fovy = self.zoom.to_radians();
+        // Maps 0..1 clip depth to 1..0.
+        let reverse = glm::mat4(
+            1f32, 0f32, 0f32, 0f32, //
+            0f32, 1f32, 0f32, 0f32, //
+            0f32, 0f32, -1f32, 1f32, //
+            0f32, 0f32, 0f32, 1f32,
+        );
+        let perspective = match (self.depth_mode, self.infinite_far) {
+            (DepthMode::Standard, false) => {
+                glm::perspective(self.aspect_ratio, fovy, self.near, self.far)
+            }
+            (DepthMode::Standard, true) => {
+                glm::infinite_perspective_rh_no(self.aspect_ratio, fovy, self.near)
+            }
+            (DepthMode::ReverseZ, false) => {
+                reverse * glm::perspective_rh_zo(self.aspect_ratio, fovy, self.near, self.far)
+            }
+            (DepthMode::ReverseZ, true) => {
+                glm::reversed_infinite_perspective_rh_zo(self.aspect_ratio, fovy, self.near)
+            }
+        };
+        if self.projection_blend <= 0f32 {
+            return perspective;
+        }
+        let half_height = self.ortho_height / 2f32;
+        let half_width = half_height * self.aspect_ratio;
+        let orthographic = match self.depth_mode {
+            DepthMode::Standard => glm::ortho(
+                -half_width,
+                half_width,
+                -half_height,
+                half_height,
+                self.near,
+                self.far,
+            ),
+            DepthMode::ReverseZ => {
+                reverse
+                    * glm::ortho_rh_zo(
+                        -half_width,
+                        half_width,
+                        -half_height,
+                        half_height,
+                        self.near,
+                        self.far,
+                    )
+            }
+        };
+        if self.projection_blend >= 1f32 {
+            return orthographic;
+        }
+        let t = self.projection_blend;
+        perspective + (orthographic - perspective) * (t * t * (3f32 - 2f32 * t))
+    }
+
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
+
+    /// Must match the GL clip control and depth test the scene is rendered with.
+    pub fn set_depth_mode(&mut self, depth_mode: DepthMode, infinite_far: bool) {
+        self.depth_mode = depth_mode;
+        self.infinite_far = infinite_far;
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Starts blending to `projection`. The orthographic view is sized to match what the
+    /// perspective one shows at the focus distance, so the model keeps its size on screen.
+    pub fn set_projection(&mut self, projection: Projection) {
+        if projection == self.projection {
+            return;
+        }
+        if projection == Projection::Orthographic {
+            self.ortho_height = 2f32 * self.distance * (self.zoom.to_radians() / 2f32).tan();
+        }
+        self.projection = projection;
+    }
+
+    pub fn toggle_projection(&mut self) {
+        self.set_projection(match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        });
+    }
+
+    /// Flies to an orthographic orbit looking at the target from `preset`.
+    pub fn set_view_preset(&mut self, preset: ViewPreset) {
+        let target = match self.mode {
+            CameraMode::Orbit => self.target,
+            CameraMode::FreeFly => self.position + self.front * self.distance,
+        };
+        // Presets are level views, the transition takes care of any roll.
+        self.free_rotation = false;
+        (self.yaw, self.pitch) = preset.angles();
+        self.orbit_around(target, self.distance);
+        self.set_projection(Projection::Orthographic);
     }
 
     pub fn get_position(&self) -> glm::Vec3 {
         self.position
     }
 
+    /// Moves a free-fly camera; orbit cameras keep their position relative to the target.
+    pub fn set_position(&mut self, position: glm::Vec3) {
+        if self.mode == CameraMode::FreeFly {
+            self.position = position;
+        }
+    }
+
     pub fn get_front(&self) -> glm::Vec3 {
         self.front
     }
@@ -73,8 +381,33 @@ impl Camera {
         self.mouse_sensitivity
     }
 
+    pub fn get_up(&self) -> glm::Vec3 {
+        self.up
+    }
+
+    pub fn get_right(&self) -> glm::Vec3 {
+        self.right
+    }
+
+    pub fn set_speed_mode(&mut self, speed_mode: SpeedMode) {
+        self.speed_mode = speed_mode;
+    }
+
+    pub fn get_velocity(&self) -> glm::Vec3 {
+        self.velocity
+    }
+
+    fn speed(&self) -> f32 {
+        self.move_speed
+            * match self.speed_mode {
+                SpeedMode::Normal => 1f32,
+                SpeedMode::Sprint => self.motion.sprint_multiplier,
+                SpeedMode::Slow => self.motion.slow_multiplier,
+            }
+    }
+
     pub fn process_keyboard(&mut self, direction: CameraMovement, deltatime: f64) {
-        let vel: f32 = self.move_speed * (deltatime as f32);
+        let vel: f32 = self.speed() * (deltatime as f32);
         if direction.val() == 0 {
             self.position += self.front * vel;
         }
@@ -87,12 +420,104 @@ impl Camera {
         if direction.val() == 3 {
             self.position += self.right * vel;
         }
+        if direction.val() == 4 {
+            self.position += self.vertical_axis() * vel;
+        }
+        if direction.val() == 5 {
+            self.position -= self.vertical_axis() * vel;
+        }
+        if direction.val() == 6 {
+            self.process_roll(-1f32, deltatime);
+        }
+        if direction.val() == 7 {
+            self.process_roll(1f32, deltatime);
+        }
+    }
+
+    /// Analog movement, `forward`, `right` and `up` are action values where 1 is full speed. In
+    /// orbit mode `forward` dollies and the others pan the target.
+    pub fn process_movement(&mut self, forward: f32, right: f32, up: f32, deltatime: f64) {
+        let vel: f32 = self.speed() * (deltatime as f32);
+        let vertical = self.vertical_axis();
+        match self.mode {
+            CameraMode::FreeFly if self.motion.inertia => {
+                let dt = deltatime as f32;
+                let wish =
+                    (self.front * forward + self.right * right + vertical * up) * self.speed();
+                if wish == glm::Vec3::zeros() {
+                    self.velocity *= (-self.motion.damping * dt).exp();
+                } else {
+                    let change = wish - self.velocity;
+                    let max_change = self.motion.acceleration * dt;
+                    if change.norm() <= max_change {
+                        self.velocity = wish;
+                    } else {
+                        self.velocity += change.normalize() * max_change;
+                    }
+                }
+                self.position += self.velocity * dt;
+            }
+            CameraMode::FreeFly => {
+                self.position += self.front * forward * vel;
+                self.position += self.right * right * vel;
+                self.position += vertical * up * vel;
+            }
+            CameraMode::Orbit => {
+                if forward == 0f32 && right == 0f32 && up == 0f32 {
+                    return;
+                }
+                self.distance = (self.distance - forward * vel).max(MIN_DISTANCE);
+                self.target += self.right * right * vel;
+                self.target += vertical * up * vel;
+                self.update_vectors();
+            }
+        }
     }
 
-    pub fn process_mouse(&mut self, mut xoffset: f32, mut yoffset: f32) {
+    /// Up and down movement follows the camera's own up axis in free rotation, and the world's
+    /// otherwise.
+    fn vertical_axis(&self) -> glm::Vec3 {
+        if self.free_rotation {
+            self.up
+        } else {
+            self.world_up
+        }
+    }
+
+    /// Rolls clockwise for positive `amount`, where 1 is full speed. Only applies with free
+    /// rotation, yaw/pitch cameras stay level.
+    pub fn process_roll(&mut self, amount: f32, deltatime: f64) {
+        if !self.free_rotation || amount == 0f32 {
+            return;
+        }
+        let angle = (amount * ROLL_SPEED * deltatime as f32).to_radians();
+        self.orientation = (self.orientation
+            * glm::quat_angle_axis(angle, &glm::vec3(0f32, 0f32, -1f32)))
+        .normalize();
+        self.update_vectors();
+    }
+
+    /// Turns by mouse offsets in pixels, eased in over [`CameraMotion::look_smoothing`].
+    pub fn process_mouse(&mut self, xoffset: f32, yoffset: f32) {
+        if self.motion.look_smoothing > 0f32 {
+            self.pending_look += glm::vec2(xoffset, yoffset);
+        } else {
+            self.apply_look(xoffset, yoffset);
+        }
+    }
+
+    fn apply_look(&mut self, mut xoffset: f32, mut yoffset: f32) {
         xoffset *= self.mouse_sensitivity;
         yoffset *= self.mouse_sensitivity;
 
+        if self.free_rotation {
+            let yaw = glm::quat_angle_axis((-xoffset).to_radians(), &glm::vec3(0f32, 1f32, 0f32));
+            let pitch = glm::quat_angle_axis(yoffset.to_radians(), &glm::vec3(1f32, 0f32, 0f32));
+            self.orientation = (self.orientation * yaw * pitch).normalize();
+            self.update_vectors();
+            return;
+        }
+
         self.yaw += xoffset;
         self.pitch += yoffset;
         if self.constrain_pitch {
@@ -106,7 +531,30 @@ impl Camera {
         self.update_vectors();
     }
 
+    /// Moves the orbit target in the view plane, by mouse offsets in pixels.
+    pub fn process_pan(&mut self, xoffset: f32, yoffset: f32) {
+        if self.mode != CameraMode::Orbit {
+            return;
+        }
+        let scale = self.distance * PAN_SPEED;
+        self.target -= self.right * xoffset * scale;
+        self.target -= self.up * yoffset * scale;
+        self.update_vectors();
+    }
+
+    /// Changes the FOV, the orthographic view height, or dollies towards the target in orbit
+    /// mode.
     pub fn process_scroll(&mut self, yoffset: f32) {
+        if self.projection == Projection::Orthographic {
+            self.ortho_height =
+                (self.ortho_height * DOLLY_STEP.powf(yoffset)).max(MIN_ORTHO_HEIGHT);
+            return;
+        }
+        if self.mode == CameraMode::Orbit {
+            self.distance = (self.distance * DOLLY_STEP.powf(yoffset)).max(MIN_DISTANCE);
+            self.update_vectors();
+            return;
+        }
         self.zoom -= yoffset;
         if self.zoom < MIN_ZOOM {
             self.zoom = MIN_ZOOM;
@@ -116,15 +564,222 @@ impl Camera {
         }
     }
 
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    pub fn get_target(&self) -> glm::Vec3 {
+        self.target
+    }
+
+    /// Moves the orbit target without a transition, keeping the current distance.
+    pub fn set_target(&mut self, target: glm::Vec3) {
+        self.target = target;
+        if self.mode == CameraMode::Orbit {
+            self.update_vectors();
+        }
+    }
+
+    pub fn orientation(&self) -> glm::Quat {
+        self.orientation
+    }
+
+    pub fn free_rotation(&self) -> bool {
+        self.free_rotation
+    }
+
+    /// Switches between yaw/pitch and flight-style rotation. Leaving free rotation levels the
+    /// camera out over a short transition.
+    pub fn set_free_rotation(&mut self, free_rotation: bool) {
+        if free_rotation == self.free_rotation {
+            return;
+        }
+        self.settle();
+        if !free_rotation {
+            self.start_transition();
+        }
+        self.free_rotation = free_rotation;
+        self.update_vectors();
+    }
+
+    pub fn toggle_free_rotation(&mut self) {
+        self.set_free_rotation(!self.free_rotation);
+    }
+
+    /// Turns to `orientation`; cameras without free rotation keep its direction but drop the roll.
+    pub fn set_orientation(&mut self, orientation: glm::Quat) {
+        self.transition = None;
+        self.orientation = orientation.normalize();
+        if !self.free_rotation {
+            self.look_along(glm::quat_rotate_vec3(
+                &self.orientation,
+                &glm::vec3(0f32, 0f32, -1f32),
+            ));
+        }
+        self.update_vectors();
+    }
+
+    /// Turns a fraction `t` of the way to `orientation` along the shortest arc.
+    pub fn rotate_towards(&mut self, orientation: glm::Quat, t: f32) {
+        let current = basis_to_orientation(&self.front, &self.up);
+        self.set_orientation(glm::quat_slerp(&current, &orientation, t));
+    }
+
+    /// Switches controllers without moving the view: orbiting starts around the point
+    /// `distance` in front of the camera, and free-fly continues from the current pose.
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == self.mode {
+            return;
+        }
+        self.velocity = glm::Vec3::zeros();
+        match mode {
+            CameraMode::Orbit => self.target = self.position + self.front * self.distance,
+            CameraMode::FreeFly => {
+                if self.transition.is_some() {
+                    // Keep the blended pose rather than jumping to the end of the flight.
+                    self.settle();
+                }
+            }
+        }
+        self.mode = mode;
+        self.update_vectors();
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.set_mode(match self.mode {
+            CameraMode::FreeFly => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::FreeFly,
+        });
+    }
+
+    /// Flies to an orbit around `target` at `distance`, keeping the viewing direction.
+    pub fn orbit_around(&mut self, target: glm::Vec3, distance: f32) {
+        self.start_transition();
+        self.mode = CameraMode::Orbit;
+        self.target = target;
+        self.distance = distance.max(MIN_DISTANCE);
+        self.update_vectors();
+    }
+
+    /// Orbits the centre of the box `min`..`max` from far enough away for all of it to be in view.
+    pub fn frame_bounds(&mut self, min: glm::Vec3, max: glm::Vec3) {
+        let radius = glm::distance(&min, &max) / 2f32;
+        let vertical = self.zoom.to_radians();
+        let horizontal = 2f32 * ((vertical / 2f32).tan() * self.aspect_ratio).atan();
+        let fov = vertical.min(horizontal);
+        let distance = radius / (fov / 2f32).sin() + self.near;
+        self.orbit_around((min + max) / 2f32, distance);
+        if self.projection == Projection::Orthographic {
+            self.ortho_height = 2f32 * radius * (1f32 / self.aspect_ratio).max(1f32);
+        }
+    }
+
+    /// Advances running transitions and smoothed mouse-look, call once per simulation tick.
+    pub fn update(&mut self, deltatime: f64) {
+        if self.pending_look != glm::Vec2::zeros() {
+            let fraction = if self.motion.look_smoothing > 0f32 {
+                1f32 - (-(deltatime as f32) / self.motion.look_smoothing).exp()
+            } else {
+                1f32
+            };
+            let look = self.pending_look * fraction;
+            self.pending_look -= look;
+            self.apply_look(look.x, look.y);
+        }
+        let step = deltatime as f32 / PROJECTION_TIME;
+        self.projection_blend = match self.projection {
+            Projection::Perspective => (self.projection_blend - step).max(0f32),
+            Projection::Orthographic => (self.projection_blend + step).min(1f32),
+        };
+        if let Some(transition) = &mut self.transition {
+            transition.elapsed += deltatime as f32;
+            if transition.elapsed >= TRANSITION_TIME {
+                self.transition = None;
+            }
+            self.update_vectors();
+        }
+    }
+
+    fn start_transition(&mut self) {
+        self.transition = Some(Transition {
+            from_position: self.position,
+            from_orientation: basis_to_orientation(&self.front, &self.up),
+            elapsed: 0f32,
+        });
+    }
+
+    /// Makes the current, possibly mid-transition, pose the one the camera rests in.
+    fn settle(&mut self) {
+        self.transition = None;
+        self.orientation = basis_to_orientation(&self.front, &self.up);
+        self.look_along(self.front);
+        if self.mode == CameraMode::Orbit {
+            self.target = self.position + self.front * self.distance;
+        }
+    }
+
+    /// Points the camera along `direction` by setting yaw and pitch.
+    fn look_along(&mut self, direction: glm::Vec3) {
+        let direction = direction.normalize();
+        self.pitch = direction.y.clamp(-1f32, 1f32).asin().to_degrees();
+        self.yaw = direction.z.atan2(direction.x).to_degrees();
+    }
+
     fn update_vectors(&mut self) {
-        let front = glm::vec3(
-            self.yaw.to_radians().cos() * self.pitch.to_radians().cos(),
-            self.pitch.to_radians().sin(),
-            self.yaw.to_radians().sin() * self.pitch.to_radians().cos(),
-        );
-        self.front = front.normalize();
-        self.right = glm::cross(&self.front, &self.world_up).normalize();
-        self.up = glm::cross(&self.right, &self.front).normalize();
+        if self.free_rotation {
+            self.set_basis(self.orientation);
+        } else {
+            let front = glm::vec3(
+                self.yaw.to_radians().cos() * self.pitch.to_radians().cos(),
+                self.pitch.to_radians().sin(),
+                self.yaw.to_radians().sin() * self.pitch.to_radians().cos(),
+            );
+            self.front = front.normalize();
+            self.right = glm::cross(&self.front, &self.world_up).normalize();
+            self.up = glm::cross(&self.right, &self.front).normalize();
+            self.orientation = basis_to_orientation(&self.front, &self.up);
+        }
+        if self.mode == CameraMode::Orbit {
+            self.position = self.target - self.front * self.distance;
+        }
+        if let Some(transition) = &self.transition {
+            let t = (transition.elapsed / TRANSITION_TIME).clamp(0f32, 1f32);
+            let t = t * t * (3f32 - 2f32 * t);
+            if self.mode == CameraMode::Orbit {
+                self.position = glm::lerp(&transition.from_position, &self.position, t);
+            }
+            self.set_basis(glm::quat_slerp(
+                &transition.from_orientation,
+                &self.orientation,
+                t,
+            ));
+        }
+    }
+
+    fn set_basis(&mut self, orientation: glm::Quat) {
+        self.front = glm::quat_rotate_vec3(&orientation, &glm::vec3(0f32, 0f32, -1f32));
+        self.up = glm::quat_rotate_vec3(&orientation, &glm::vec3(0f32, 1f32, 0f32));
+        self.right = glm::quat_rotate_vec3(&orientation, &glm::vec3(1f32, 0f32, 0f32));
+    }
+
+    /// Blends from `previous` towards `self`, for rendering between fixed simulation ticks.
+    pub fn interpolate(&self, previous: &Camera, alpha: f32) -> Camera {
+        let mut camera = *self;
+        camera.position = glm::lerp(&previous.position, &self.position, alpha);
+        // Slerp rather than lerping `front`, which is degenerate when the two fronts are opposite.
+        camera.set_basis(glm::quat_slerp(
+            &basis_to_orientation(&previous.front, &previous.up),
+            &basis_to_orientation(&self.front, &self.up),
+            alpha,
+        ));
+        camera.zoom = previous.zoom + (self.zoom - previous.zoom) * alpha;
+        camera.target = glm::lerp(&previous.target, &self.target, alpha);
+        camera.distance = previous.distance + (self.distance - previous.distance) * alpha;
+        camera.ortho_height =
+            previous.ortho_height + (self.ortho_height - previous.ortho_height) * alpha;
+        camera.projection_blend =
+            previous.projection_blend + (self.projection_blend - previous.projection_blend) * alpha;
+        camera
     }
 
     pub fn set_aspect_ratio_xy(&mut self, x: f32, y: f32) {
@@ -179,10 +834,16 @@ pub fn CameraConstructor(
         world_up,
         yaw,
         pitch,
+        orientation: basis_to_orientation(&front, &up),
+        free_rotation: false,
         mouse_sensitivity: match mouse_sensitivity {
             Some(n) => n,
             None => SENSITIVITY,
         },
+        motion: CameraMotion::default(),
+        speed_mode: SpeedMode::Normal,
+        velocity: glm::vec3(0f32, 0f32, 0f32),
+        pending_look: glm::vec2(0f32, 0f32),
         move_speed: match move_speed {
             Some(n) => n,
             None => SPEED,
@@ -207,69 +868,14 @@ pub fn CameraConstructor(
             Some(n) => n,
             None => 100f32,
         },
-    }
-}
-
-#[derive(Debug)]
-pub struct cpp_camera {
-    pub camera: Option<Camera>,
-}
-
-impl cpp_camera {
-    pub fn new(camera: Camera) -> Self {
-        Self {
-            camera: Some(camera),
-        }
-    }
-
-    pub fn get_view_matrix(&self) -> glm::Mat4 {
-        self.camera
-            .expect("Camera not initialized")
-            .get_view_matrix()
-    }
-
-    pub fn get_projection_matrix(&self) -> glm::Mat4 {
-        self.camera
-            .expect("Camera not initialized")
-            .get_projection_matrix()
-    }
-
-    pub fn get_position(&self) -> glm::Vec3 {
-        self.camera.expect("Camera not initialized").get_position()
-    }
-
-    pub fn get_front(&self) -> glm::Vec3 {
-        self.camera.expect("Camera not initialized").get_front()
-    }
-
-    pub fn get_sensitivity(&self) -> f32 {
-        self.camera
-            .expect("Camera not initialized")
-            .get_sensitivity()
-    }
-
-    pub fn process_keyboard(&mut self, direction: CameraMovement, deltatime: f64) {
-        as_mut_expect!(self.camera, "Camera not initialized")
-            .process_keyboard(direction, deltatime);
-    }
-
-    pub fn process_mouse(&mut self, xoffset: f32, yoffset: f32) {
-        as_mut_expect!(self.camera, "Camera not initialized").process_mouse(xoffset, yoffset);
-    }
-
-    pub fn process_scroll(&mut self, yoffset: f32) {
-        as_mut_expect!(self.camera, "Camera not initialized").process_scroll(yoffset);
-    }
-
-    fn update_vectors(&mut self) {
-        as_mut_expect!(self.camera, "Camera not initialized").update_vectors();
-    }
-
-    pub fn set_aspect_ratio_xy(&mut self, x: f32, y: f32) {
-        as_mut_expect!(self.camera, "Camera not initialized").set_aspect_ratio(x / y);
-    }
-
-    pub fn set_aspect_ratio(&mut self, aspect: f32) {
-        as_mut_expect!(self.camera, "Camera not initialized").set_aspect_ratio(aspect);
+        mode: CameraMode::FreeFly,
+        target: glm::vec3(0f32, 0f32, 0f32),
+        distance: ORBIT_DISTANCE,
+        transition: None,
+        projection: Projection::Perspective,
+        ortho_height: ORTHO_HEIGHT,
+        depth_mode: DepthMode::Standard,
+        infinite_far: false,
+        projection_blend: 0f32,
     }
 }
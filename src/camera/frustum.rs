@@ -0,0 +1,58 @@
+use nalgebra_glm as glm;
+
+use crate::asset_management::bounds::BoundingVolume;
+
+/// The six clip planes of a view-projection matrix as `(normal, distance)`, normals pointing
+/// inwards: left, right, bottom, top, near, far.
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    pub planes: [glm::Vec4; 6],
+}
+
+impl Frustum {
+    /// Gribb/Hartmann plane extraction from a combined `projection * view` matrix.
+    pub fn from_matrix(view_projection: &glm::Mat4) -> Self {
+        let row = |i: usize| view_projection.row(i).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let planes = [w + x, w - x, w + y, w - y, w + z, w - z].map(|plane| {
+            let length = plane.xyz().norm();
+            if length > 0f32 { plane / length } else { plane }
+        });
+        Self { planes }
+    }
+
+    fn distance(plane: &glm::Vec4, point: &glm::Vec3) -> f32 {
+        plane.xyz().dot(point) + plane.w
+    }
+
+    pub fn contains_point(&self, point: &glm::Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(plane, point) >= 0f32)
+    }
+
+    pub fn intersects_sphere(&self, center: &glm::Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(plane, center) >= -radius)
+    }
+
+    /// Tests the corner of the box furthest along each plane's normal; conservative near the
+    /// frustum's edges, where a box outside it can still pass.
+    pub fn intersects_aabb(&self, min: &glm::Vec3, max: &glm::Vec3) -> bool {
+        self.planes.iter().all(|plane| {
+            let corner = glm::vec3(
+                if plane.x >= 0f32 { max.x } else { min.x },
+                if plane.y >= 0f32 { max.y } else { min.y },
+                if plane.z >= 0f32 { max.z } else { min.z },
+            );
+            Self::distance(plane, &corner) >= 0f32
+        })
+    }
+
+    /// Sphere test first since it is cheaper, then the box.
+    pub fn intersects(&self, bounds: &BoundingVolume) -> bool {
+        self.intersects_sphere(&bounds.center, bounds.radius)
+            && self.intersects_aabb(&bounds.min, &bounds.max)
+    }
+}
@@ -0,0 +1,337 @@
+use std::path::Path;
+
+use nalgebra_glm as glm;
+use serde::{Deserialize, Serialize};
+
+use super::{Camera, CameraMode};
+
+/// Shapes the playback speed over the whole path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps progress 0..1 to eased progress 0..1.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0f32, 1f32);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2f32 - t),
+            Easing::EaseInOut => t * t * (3f32 - 2f32 * t),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    /// Seconds from the start of the path.
+    pub time: f32,
+    pub position: glm::Vec3,
+    pub orientation: glm::Quat,
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+}
+
+impl Keyframe {
+    pub fn from_camera(camera: &Camera, time: f32) -> Self {
+        Self {
+            time,
+            position: camera.get_position(),
+            orientation: camera.orientation(),
+            fov: camera.zoom,
+        }
+    }
+}
+
+/// [`Keyframe`] as written in path files.
+#[derive(Serialize, Deserialize)]
+struct KeyframeDef {
+    time: f32,
+    position: [f32; 3],
+    /// Quaternion as `[x, y, z, w]`.
+    orientation: [f32; 4],
+    fov: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CameraPathDef {
+    #[serde(default)]
+    easing: Easing,
+    keyframes: Vec<KeyframeDef>,
+}
+
+/// Keyframes joined by a Catmull-Rom spline, for fly-throughs and repeatable benchmark runs.
+#[derive(Clone, Debug, Default)]
+pub struct CameraPath {
+    /// Sorted by time.
+    keyframes: Vec<Keyframe>,
+    pub easing: Easing,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Inserts `keyframe` in time order, replacing one at the same time.
+    pub fn add_keyframe(&mut self, keyframe: Keyframe) {
+        match self
+            .keyframes
+            .binary_search_by(|k| k.time.total_cmp(&keyframe.time))
+        {
+            Ok(i) => self.keyframes[i] = keyframe,
+            Err(i) => self.keyframes.insert(i, keyframe),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0f32, |k| k.time)
+    }
+
+    /// The camera pose `time` seconds into playback, after easing.
+    pub fn sample(&self, time: f32) -> Option<Keyframe> {
+        let first = self.keyframes.first()?;
+        let duration = self.duration() - first.time;
+        if duration <= 0f32 {
+            return Some(*first);
+        }
+        let time = first.time + self.easing.apply(time / duration) * duration;
+
+        let i = self
+            .keyframes
+            .partition_point(|k| k.time <= time)
+            .clamp(1, self.keyframes.len() - 1);
+        let (k1, k2) = (&self.keyframes[i - 1], &self.keyframes[i]);
+        // Past the ends, mirror the neighbouring keyframe so the curve leaves straight.
+        let p0 = match i.checked_sub(2) {
+            Some(j) => self.keyframes[j].position,
+            None => k1.position * 2f32 - k2.position,
+        };
+        let p3 = match self.keyframes.get(i + 1) {
+            Some(k) => k.position,
+            None => k2.position * 2f32 - k1.position,
+        };
+        let f0 = i.checked_sub(2).map_or(k1.fov, |j| self.keyframes[j].fov);
+        let f3 = self.keyframes.get(i + 1).map_or(k2.fov, |k| k.fov);
+
+        let u = ((time - k1.time) / (k2.time - k1.time)).clamp(0f32, 1f32);
+        Some(Keyframe {
+            time,
+            position: catmull_rom(p0, k1.position, k2.position, p3, u),
+            orientation: glm::quat_slerp(&k1.orientation, &k2.orientation, u),
+            fov: catmull_rom(
+                glm::vec1(f0),
+                glm::vec1(k1.fov),
+                glm::vec1(k2.fov),
+                glm::vec1(f3),
+                u,
+            )
+            .x,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let yaml = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Self::from_yaml(&yaml).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_yaml())
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, String> {
+        let def: CameraPathDef =
+            serde_yaml::from_str(yaml).map_err(|e| format!("Invalid camera path: {}", e))?;
+        let mut path = CameraPath {
+            keyframes: Vec::new(),
+            easing: def.easing,
+        };
+        for k in def.keyframes {
+            let [x, y, z, w] = k.orientation;
+            path.add_keyframe(Keyframe {
+                time: k.time,
+                position: glm::make_vec3(&k.position),
+                orientation: glm::quat(x, y, z, w).normalize(),
+                fov: k.fov,
+            });
+        }
+        Ok(path)
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let def = CameraPathDef {
+            easing: self.easing,
+            keyframes: self
+                .keyframes
+                .iter()
+                .map(|k| KeyframeDef {
+                    time: k.time,
+                    position: k.position.into(),
+                    orientation: k.orientation.coords.into(),
+                    fov: k.fov,
+                })
+                .collect(),
+        };
+        serde_yaml::to_string(&def).unwrap()
+    }
+}
+
+fn catmull_rom<const D: usize>(
+    p0: glm::TVec<f32, D>,
+    p1: glm::TVec<f32, D>,
+    p2: glm::TVec<f32, D>,
+    p3: glm::TVec<f32, D>,
+    u: f32,
+) -> glm::TVec<f32, D> {
+    let (u2, u3) = (u * u, u * u * u);
+    (p1 * 2f32
+        + (p2 - p0) * u
+        + (p0 * 2f32 - p1 * 5f32 + p2 * 4f32 - p3) * u2
+        + (p1 * 3f32 - p0 - p2 * 3f32 + p3) * u3)
+        * 0.5f32
+}
+
+/// Moves a [`Camera`] along a [`CameraPath`].
+#[derive(Clone, Debug)]
+pub struct CameraPathPlayer {
+    pub path: CameraPath,
+    time: f32,
+    pub looping: bool,
+}
+
+impl CameraPathPlayer {
+    pub fn new(path: CameraPath) -> Self {
+        Self {
+            path,
+            time: 0f32,
+            looping: false,
+        }
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.path.duration() - self.start()
+    }
+
+    fn start(&self) -> f32 {
+        self.path.keyframes().first().map_or(0f32, |k| k.time)
+    }
+
+    /// Advances playback by `deltatime` seconds and poses `camera`, which is switched to
+    /// free-fly with free rotation so the path's roll is kept.
+    pub fn update(&mut self, camera: &mut Camera, deltatime: f64) {
+        self.time += deltatime as f32;
+        let length = self.path.duration() - self.start();
+        if self.looping && length > 0f32 {
+            self.time %= length;
+        }
+        let Some(key) = self.path.sample(self.time.min(length)) else {
+            return;
+        };
+        camera.set_mode(CameraMode::FreeFly);
+        camera.set_free_rotation(true);
+        camera.set_position(key.position);
+        camera.set_orientation(key.orientation);
+        camera.zoom = key.fov;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f32, position: [f32; 3], yaw: f32, fov: f32) -> Keyframe {
+        Keyframe {
+            time,
+            position: glm::make_vec3(&position),
+            orientation: glm::quat_angle_axis(yaw.to_radians(), &glm::vec3(0f32, 1f32, 0f32)),
+            fov,
+        }
+    }
+
+    fn path() -> CameraPath {
+        let mut path = CameraPath::new();
+        path.easing = Easing::Linear;
+        path.add_keyframe(keyframe(0f32, [0f32, 0f32, 0f32], 0f32, 45f32));
+        path.add_keyframe(keyframe(1f32, [4f32, 1f32, 0f32], 30f32, 60f32));
+        path.add_keyframe(keyframe(3f32, [4f32, 1f32, -6f32], 90f32, 30f32));
+        path
+    }
+
+    fn assert_pose(actual: &Keyframe, expected: &Keyframe) {
+        assert!(
+            glm::distance(&actual.position, &expected.position) < 1e-4,
+            "{:?} != {:?}",
+            actual.position,
+            expected.position
+        );
+        assert!(glm::quat_dot(&actual.orientation, &expected.orientation).abs() > 1f32 - 1e-5);
+        assert!((actual.fov - expected.fov).abs() < 1e-4);
+    }
+
+    #[test]
+    fn linear_sampling_hits_every_keyframe() {
+        let path = path();
+        for keyframe in path.keyframes() {
+            assert_pose(&path.sample(keyframe.time).unwrap(), keyframe);
+        }
+    }
+
+    #[test]
+    fn add_keyframe_keeps_time_order_and_replaces_equal_times() {
+        let mut path = path();
+        path.add_keyframe(keyframe(2f32, [1f32, 1f32, 1f32], 0f32, 50f32));
+        path.add_keyframe(keyframe(1f32, [9f32, 9f32, 9f32], 0f32, 70f32));
+        let times: Vec<_> = path.keyframes().iter().map(|k| k.time).collect();
+        assert_eq!(times, [0f32, 1f32, 2f32, 3f32]);
+        assert_eq!(path.keyframes()[1].fov, 70f32);
+    }
+
+    #[test]
+    fn yaml_round_trip_keeps_the_keyframes() {
+        let mut path = path();
+        path.easing = Easing::EaseOut;
+        let loaded = CameraPath::from_yaml(&path.to_yaml()).unwrap();
+        assert_eq!(loaded.easing, Easing::EaseOut);
+        assert_eq!(loaded.keyframes().len(), path.keyframes().len());
+        for (loaded, original) in loaded.keyframes().iter().zip(path.keyframes()) {
+            assert_eq!(loaded.time, original.time);
+            assert_pose(loaded, original);
+        }
+    }
+
+    #[test]
+    fn single_keyframe_is_returned_as_is() {
+        let mut path = CameraPath::new();
+        let only = keyframe(2f32, [1f32, 2f32, 3f32], 10f32, 40f32);
+        path.add_keyframe(only);
+        assert_eq!(path.sample(0f32), Some(only));
+        assert_eq!(path.sample(5f32), Some(only));
+        assert_eq!(CameraPath::new().sample(0f32), None);
+    }
+}
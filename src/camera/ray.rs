@@ -0,0 +1,158 @@
+use nalgebra_glm as glm;
+
+/// A half-line from `origin` along the unit vector `direction`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: glm::Vec3,
+    pub direction: glm::Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: glm::Vec3, direction: glm::Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, distance: f32) -> glm::Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// This ray in the space `matrix` maps to, e.g. the inverse model matrix for model space.
+    /// The direction is renormalized, so distances along it change with any scale in `matrix`.
+    pub fn transformed(&self, matrix: &glm::Mat4) -> Self {
+        let origin = (matrix * self.origin.push(1f32)).xyz();
+        let direction = (matrix * self.direction.push(0f32)).xyz();
+        Self::new(origin, direction)
+    }
+
+    /// Möller–Trumbore intersection with the triangle `a`, `b`, `c`, from either side. Returns
+    /// the distance along the ray and the barycentric weights of `b` and `c`.
+    pub fn intersect_triangle(
+        &self,
+        a: &glm::Vec3,
+        b: &glm::Vec3,
+        c: &glm::Vec3,
+    ) -> Option<(f32, f32, f32)> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = glm::cross(&self.direction, &edge2);
+        let determinant = edge1.dot(&p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse = 1f32 / determinant;
+        let s = self.origin - a;
+        let u = s.dot(&p) * inverse;
+        if !(0f32..=1f32).contains(&u) {
+            return None;
+        }
+        let q = glm::cross(&s, &edge1);
+        let v = self.direction.dot(&q) * inverse;
+        if v < 0f32 || u + v > 1f32 {
+            return None;
+        }
+        let t = edge2.dot(&q) * inverse;
+        (t > 0f32).then_some((t, u, v))
+    }
+
+    /// Distance to the nearest point of the sphere in front of the origin, 0 when inside it.
+    pub fn intersect_sphere(&self, center: &glm::Vec3, radius: f32) -> Option<f32> {
+        let to_center = center - self.origin;
+        let along = to_center.dot(&self.direction);
+        let distance_squared = to_center.norm_squared() - along * along;
+        if distance_squared > radius * radius {
+            return None;
+        }
+        let half_chord = (radius * radius - distance_squared).sqrt();
+        let far = along + half_chord;
+        (far >= 0f32).then_some((along - half_chord).max(0f32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> [glm::Vec3; 3] {
+        [
+            glm::vec3(-1f32, -1f32, 0f32),
+            glm::vec3(1f32, -1f32, 0f32),
+            glm::vec3(0f32, 1f32, 0f32),
+        ]
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn triangle_hit() {
+        let [a, b, c] = triangle();
+        let ray = Ray::new(glm::vec3(0f32, 0f32, 5f32), glm::vec3(0f32, 0f32, -1f32));
+        let (t, u, v) = ray.intersect_triangle(&a, &b, &c).unwrap();
+        assert!(close(t, 5f32));
+        assert!(close(u, 0.25f32) && close(v, 0.5f32));
+    }
+
+    #[test]
+    fn triangle_miss() {
+        let [a, b, c] = triangle();
+        let ray = Ray::new(glm::vec3(2f32, 0f32, 5f32), glm::vec3(0f32, 0f32, -1f32));
+        assert_eq!(ray.intersect_triangle(&a, &b, &c), None);
+        let parallel = Ray::new(glm::vec3(0f32, 0f32, 5f32), glm::vec3(1f32, 0f32, 0f32));
+        assert_eq!(parallel.intersect_triangle(&a, &b, &c), None);
+    }
+
+    #[test]
+    fn triangle_backface_hits() {
+        let [a, b, c] = triangle();
+        let ray = Ray::new(glm::vec3(0f32, 0f32, -3f32), glm::vec3(0f32, 0f32, 1f32));
+        let (t, _, _) = ray.intersect_triangle(&a, &b, &c).unwrap();
+        assert!(close(t, 3f32));
+    }
+
+    #[test]
+    fn triangle_behind_origin_misses() {
+        let [a, b, c] = triangle();
+        let ray = Ray::new(glm::vec3(0f32, 0f32, 5f32), glm::vec3(0f32, 0f32, 1f32));
+        assert_eq!(ray.intersect_triangle(&a, &b, &c), None);
+    }
+
+    #[test]
+    fn sphere_hit() {
+        let ray = Ray::new(glm::vec3(0f32, 0f32, 10f32), glm::vec3(0f32, 0f32, -1f32));
+        let t = ray
+            .intersect_sphere(&glm::vec3(0f32, 0f32, 0f32), 2f32)
+            .unwrap();
+        assert!(close(t, 8f32));
+    }
+
+    #[test]
+    fn sphere_miss() {
+        let ray = Ray::new(glm::vec3(3f32, 0f32, 10f32), glm::vec3(0f32, 0f32, -1f32));
+        assert_eq!(
+            ray.intersect_sphere(&glm::vec3(0f32, 0f32, 0f32), 2f32),
+            None
+        );
+    }
+
+    #[test]
+    fn sphere_from_inside_is_zero() {
+        let ray = Ray::new(glm::vec3(0f32, 1f32, 0f32), glm::vec3(1f32, 0f32, 0f32));
+        assert_eq!(
+            ray.intersect_sphere(&glm::vec3(0f32, 0f32, 0f32), 2f32),
+            Some(0f32)
+        );
+    }
+
+    #[test]
+    fn sphere_behind_origin_misses() {
+        let ray = Ray::new(glm::vec3(0f32, 0f32, 10f32), glm::vec3(0f32, 0f32, 1f32));
+        assert_eq!(
+            ray.intersect_sphere(&glm::vec3(0f32, 0f32, 0f32), 2f32),
+            None
+        );
+    }
+}
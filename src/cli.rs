@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use log::LevelFilter;
+
+use crate::settings::GlVersion;
+use crate::window::FullscreenMode;
+
+/// Model viewer. Every option here overrides the settings files.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Models to load instead of the configured scene (asset paths)
+    pub models: Vec<String>,
+
+    /// Scene file whose `shader` and `models` replace the configured scene
+    #[arg(long, value_name = "FILE")]
+    pub scene: Option<PathBuf>,
+
+    /// Extra settings file, layered over the user settings
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    #[arg(long)]
+    pub width: Option<i32>,
+
+    #[arg(long)]
+    pub height: Option<i32>,
+
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Start fullscreen: borderless (default) or exclusive
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "borderless")]
+    pub fullscreen: Option<FullscreenMode>,
+
+    #[arg(long, conflicts_with = "fullscreen")]
+    pub windowed: bool,
+
+    /// Monitor to go fullscreen on, 0 being the primary one
+    #[arg(long, value_name = "N")]
+    pub monitor: Option<usize>,
+
+    #[arg(long, overrides_with = "no_vsync")]
+    pub vsync: bool,
+
+    #[arg(long)]
+    pub no_vsync: bool,
+
+    /// Multisample count, 0 to disable
+    #[arg(long, value_name = "N")]
+    pub msaa: Option<u32>,
+
+    /// OpenGL context version, e.g. 3.3 or 4.6
+    #[arg(long, value_name = "MAJOR.MINOR")]
+    pub gl_version: Option<GlVersion>,
+
+    /// Use a reverse-Z float depth buffer with an infinite far plane
+    #[arg(long)]
+    pub reverse_z: bool,
+
+    /// Recompile shaders when they change on disk, watching DIR (e.g. src/assets) if given
+    #[arg(long, value_name = "DIR", num_args = 0..=1)]
+    pub hot_reload: Option<Option<PathBuf>>,
+
+    /// off, error, warn, info, debug or trace
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<LevelFilter>,
+
+    /// Render FRAMES frames offscreen and write them as PNGs instead of opening a window
+    #[arg(long, value_name = "FRAMES")]
+    pub headless: Option<u32>,
+
+    /// Output directory for --headless
+    #[arg(long, value_name = "DIR", default_value = "headless")]
+    pub output: PathBuf,
+
+    /// Compare --headless frames against the PNGs in this directory
+    #[arg(long, value_name = "DIR")]
+    pub reference: Option<PathBuf>,
+
+    /// Largest per-channel difference still counted as a match
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    pub tolerance: u8,
+
+    /// Record input and frame timing to FILE
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Replay input recorded with --record
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Camera path to play from the start if it exists, and to save recorded keyframes to
+    #[arg(long, value_name = "FILE")]
+    pub camera_path: Option<PathBuf>,
+
+    /// Loop --camera-path instead of stopping at its last keyframe
+    #[arg(long, requires = "camera_path")]
+    pub loop_path: bool,
+}
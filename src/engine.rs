@@ -0,0 +1,781 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_void};
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use glfw::ffi::*;
+use log::{debug, error, info};
+use nalgebra_glm as glm;
+
+use crate::camera::{
+    Camera, CameraConstructor, CameraMode, CameraPath, CameraPathPlayer, Keyframe, SpeedMode,
+    ViewPreset,
+};
+use crate::framebuffer::Framebuffer;
+use crate::gui::{ErrorOverlay, StatsOverlay};
+use crate::input::InputMap;
+use crate::input::gamepad::{self, GamepadConfig, GamepadDevice, GamepadState};
+use crate::input::recording::{InputRecorder, InputReplay, Record, RecordingInfo, TickInput};
+use crate::settings::WindowSettings;
+use crate::shader::ShaderWatcher;
+use crate::window::Window;
+
+pub mod debug_draw;
+pub mod depth;
+pub mod event;
+pub mod headless;
+pub mod stats;
+pub mod timestep;
+pub mod viewport;
+pub use debug_draw::DebugDraw;
+pub use depth::DepthSetup;
+pub use event::{Event, EventQueue};
+pub use headless::HeadlessOptions;
+pub use stats::Stats;
+pub use timestep::FixedTimestep;
+pub use viewport::{Viewport, ViewportRect};
+
+/// Default file for `path_save`.
+const CAMERA_PATH_FILE: &str = "camera_path.yml";
+/// Name of the viewport added by `toggle_minimap`.
+const MINIMAP: &str = "minimap";
+
+/// Where the engine's input comes from.
+pub enum InputMode {
+    Live,
+    /// Live input, also written to a recording.
+    Recording(InputRecorder),
+    /// Input and frame times read back from a recording; live mouse and gamepad input is ignored.
+    Replaying(InputReplay),
+}
+
+/// A demo/game driven by [`Engine::run`].
+pub trait Application {
+    fn init(&mut self, engine: &mut Engine);
+    /// Called once per fixed simulation tick; `dt` is always [`FixedTimestep::tick`].
+    fn update(&mut self, _engine: &mut Engine, _dt: f64) {}
+    /// `alpha` is how far the simulation is between the previous and the current tick.
+    fn render(&mut self, engine: &mut Engine, alpha: f32);
+    fn on_event(&mut self, _engine: &mut Engine, _event: &Event) {}
+}
+
+/// Owns the window, the camera and the frame timing that used to live in `static mut`s.
+pub struct Engine {
+    pub window: Window,
+    app: Option<Box<dyn Application>>,
+    pub camera: Camera,
+    previous_camera: Camera,
+    /// Where the main camera is drawn.
+    main_rect: ViewportRect,
+    /// Extra cameras drawn alongside the main one.
+    viewports: Vec<Viewport>,
+    /// Index into `viewports` of the view being rendered, `None` for the main camera.
+    active_view: Option<usize>,
+    debug_draw: Option<DebugDraw>,
+    pub timestep: FixedTimestep,
+    alpha: f32,
+    pub input: InputMap,
+    /// The pad feeding `input`; replace it with a [`gamepad::MockGamepad`] to inject recorded states.
+    pub gamepad: Option<Box<dyn GamepadDevice>>,
+    input_mode: InputMode,
+    /// Filled by the GLFW callbacks through the window user pointer, which points here rather
+    /// than at the engine so the callbacks never alias `&mut Engine`.
+    events: Box<RefCell<EventQueue>>,
+    /// Events handled since the last `glfwPollEvents`, for code that reads input outside
+    /// [`Application::on_event`].
+    frame_events: Vec<Event>,
+    pub stats: Stats,
+    stats_overlay: StatsOverlay,
+    /// Recompiles shaders whose files change, set with [`Engine::watch_shaders`].
+    pub shader_watcher: Option<ShaderWatcher>,
+    error_overlay: ErrorOverlay,
+    depth: DepthSetup,
+    /// Offscreen target the scene is drawn into when it needs a depth format the window can't
+    /// provide.
+    scene_target: Option<Framebuffer>,
+    /// Keyframes added with the `path_keyframe` action.
+    pub camera_path: CameraPath,
+    /// Seconds of simulation since the first recorded keyframe.
+    camera_path_clock: f64,
+    camera_path_file: PathBuf,
+    path_player: Option<CameraPathPlayer>,
+    last_frame: f64,
+    delta_time: f64,
+}
+
+impl Engine {
+    pub fn new(settings: &WindowSettings) -> Result<Self, String> {
+        unsafe {
+            if glfwInit() == 0 {
+                error!("GLFW failed to initialize!");
+                return Err("GLFW failed to initialize!".to_string());
+            }
+
+            // Reverse-Z renders into a multisampled offscreen target instead, which is resolved
+            // into the window.
+            let mut window_settings = settings.clone();
+            if settings.reverse_z {
+                window_settings.msaa = 0;
+            }
+            let window = match Window::new(&window_settings) {
+                Ok(window) => window,
+                Err(e) => {
+                    glfwTerminate();
+                    return Err(e);
+                }
+            };
+            let handle = window.handle();
+
+            let mut _procname = CString::new("");
+            gl::load_with(|name| {
+                _procname = CString::new(name);
+                glfwGetProcAddress(_procname.as_mut().unwrap().as_ptr()) as *const _
+            });
+            debug!(
+                "gl_proc: {:#?}",
+                glfwGetProcAddress(_procname.as_mut().unwrap().as_ptr())
+            );
+            let (width, height) = window.framebuffer_size();
+            gl::Viewport(0, 0, width, height);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Enable(gl::CULL_FACE);
+            if settings.msaa > 0 {
+                gl::Enable(gl::MULTISAMPLE);
+            }
+            let depth = depth::configure(settings.reverse_z);
+            let scene_target = if settings.reverse_z {
+                Some(Framebuffer::with_format(
+                    width,
+                    height,
+                    depth.format,
+                    settings.msaa as i32,
+                )?)
+            } else {
+                None
+            };
+
+            event::install_callbacks(handle);
+            glfwSetInputMode(handle, CURSOR, CURSOR_DISABLED);
+            gamepad::init_gamepads();
+
+            let gl_version = CStr::from_ptr(gl::GetString(gl::VERSION) as *const _)
+                .to_str()
+                .expect("Unknown Version");
+            debug!("Using OpenGL version: {}", gl_version);
+            window.set_title(&format!("{} - {}", settings.title, gl_version));
+
+            let mut camera = CameraConstructor(
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                window.aspect_ratio(),
+                None,
+                None,
+            );
+
+            // Requesting reverse-Z also removes the far plane, even where the depth range falls back.
+            camera.set_depth_mode(depth.mode, settings.reverse_z);
+
+            let mut input = InputMap::load("config/input.yml");
+            input.gamepad_config = GamepadConfig::load("config/gamepad.yml");
+
+            Ok(Self {
+                window,
+                app: None,
+                camera,
+                previous_camera: camera,
+                main_rect: ViewportRect::FULL,
+                viewports: Vec::new(),
+                active_view: None,
+                debug_draw: None,
+                timestep: FixedTimestep::default(),
+                alpha: 0f32,
+                input,
+                gamepad: gamepad::first_connected()
+                    .map(|pad| Box::new(pad) as Box<dyn GamepadDevice>),
+                input_mode: InputMode::Live,
+                events: Box::default(),
+                frame_events: Vec::new(),
+                stats: Stats::new(),
+                stats_overlay: StatsOverlay::new(),
+                shader_watcher: None,
+                error_overlay: ErrorOverlay::new(),
+                depth,
+                scene_target,
+                camera_path: CameraPath::new(),
+                camera_path_clock: 0f64,
+                camera_path_file: PathBuf::from(CAMERA_PATH_FILE),
+                path_player: None,
+                last_frame: 0f64,
+                delta_time: 0f64,
+            })
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn delta_time(&self) -> f64 {
+        self.delta_time
+    }
+
+    /// The camera of the view being rendered, blended between the last two simulation ticks.
+    pub fn render_camera(&self) -> Camera {
+        match self.active_viewport() {
+            Some(viewport) => viewport
+                .camera
+                .interpolate(&viewport.previous_camera, self.alpha),
+            None => self.camera.interpolate(&self.previous_camera, self.alpha),
+        }
+    }
+
+    /// The viewport being rendered, `None` while rendering the main camera.
+    pub fn active_viewport(&self) -> Option<&Viewport> {
+        self.active_view.and_then(|i| self.viewports.get(i))
+    }
+
+    pub fn main_rect(&self) -> ViewportRect {
+        self.main_rect
+    }
+
+    /// Moves the main camera into `rect` of the window, e.g. [`ViewportRect::LEFT_HALF`] for
+    /// split screen.
+    #[allow(dead_code)]
+    pub fn set_main_rect(&mut self, rect: ViewportRect) {
+        self.main_rect = rect;
+        self.update_aspect_ratios();
+    }
+
+    #[allow(dead_code)]
+    pub fn viewports(&self) -> &[Viewport] {
+        &self.viewports
+    }
+
+    /// Adds an extra view and returns its index. Its camera's aspect ratio is kept in sync with
+    /// the window from now on.
+    pub fn add_viewport(&mut self, viewport: Viewport) -> usize {
+        self.viewports.push(viewport);
+        self.update_aspect_ratios();
+        self.viewports.len() - 1
+    }
+
+    #[allow(dead_code)]
+    pub fn viewport_mut(&mut self, index: usize) -> Option<&mut Viewport> {
+        self.viewports.get_mut(index)
+    }
+
+    pub fn find_viewport(&self, name: &str) -> Option<usize> {
+        self.viewports.iter().position(|v| v.name == name)
+    }
+
+    /// Removes the view at `index`, shifting the ones after it down.
+    pub fn remove_viewport(&mut self, index: usize) -> Option<Viewport> {
+        (index < self.viewports.len()).then(|| self.viewports.remove(index))
+    }
+
+    fn update_aspect_ratios(&mut self) {
+        let (width, height) = self.window.framebuffer_size();
+        if let Some(aspect_ratio) = self.main_rect.aspect_ratio(width, height) {
+            self.camera.set_aspect_ratio(aspect_ratio);
+        }
+        for viewport in &mut self.viewports {
+            if let Some(aspect_ratio) = viewport.rect.aspect_ratio(width, height) {
+                viewport.camera.set_aspect_ratio(aspect_ratio);
+                viewport.previous_camera.set_aspect_ratio(aspect_ratio);
+            }
+        }
+    }
+
+    /// Adds or removes a top-down view in the top right corner that follows the main camera
+    /// and shows what it sees.
+    pub fn toggle_minimap(&mut self) {
+        if let Some(index) = self.find_viewport(MINIMAP) {
+            self.remove_viewport(index);
+            return;
+        }
+        let mut camera = self.camera;
+        camera.set_view_preset(ViewPreset::Top);
+        camera.ortho_height = self.camera.ortho_height * 2f32;
+        let mut minimap =
+            Viewport::new(MINIMAP, camera, ViewportRect::new(0.7, 0.7, 0.28, 0.28), 1);
+        minimap.show_main_frustum = true;
+        minimap.follow_main = true;
+        self.add_viewport(minimap);
+    }
+
+    /// Writes every frame time, tick input and mouse event of the next [`Engine::run`] to `path`.
+    pub fn record_input(&mut self, path: &Path) -> Result<(), String> {
+        let info = RecordingInfo {
+            tick: self.timestep.tick,
+            max_steps: self.timestep.max_steps,
+        };
+        self.input_mode = InputMode::Recording(InputRecorder::create(path, info)?);
+        Ok(())
+    }
+
+    /// Drives the next [`Engine::run`] from a recording made by [`Engine::record_input`], using
+    /// its frame times and tick rate so the simulation steps exactly as it did when recorded.
+    pub fn replay_input(&mut self, path: &Path) -> Result<(), String> {
+        let replay = InputReplay::load(path)?;
+        self.timestep = FixedTimestep::default();
+        self.timestep.tick = replay.info.tick;
+        self.timestep.max_steps = replay.info.max_steps;
+        self.input_mode = InputMode::Replaying(replay);
+        Ok(())
+    }
+
+    /// Reloads shaders from `root` whenever their sources change there. Applications pass their
+    /// own shaders to [`ShaderWatcher::reload`] each frame.
+    pub fn watch_shaders(&mut self, root: &Path) {
+        self.shader_watcher = Some(ShaderWatcher::new(root));
+    }
+
+    #[allow(dead_code)]
+    pub fn depth(&self) -> DepthSetup {
+        self.depth
+    }
+
+    fn resize_scene_target(&mut self, width: i32, height: i32) {
+        let Some(target) = &self.scene_target else {
+            return;
+        };
+        if width <= 0 || height <= 0 || (target.width, target.height) == (width, height) {
+            return;
+        }
+        match Framebuffer::with_format(width, height, self.depth.format, target.samples) {
+            Ok(target) => self.scene_target = Some(target),
+            Err(e) => error!("Could not resize the scene target: {}", e),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn input_mode(&self) -> &InputMode {
+        &self.input_mode
+    }
+
+    /// Where `path_save` writes the recorded camera path.
+    pub fn set_camera_path_file(&mut self, path: &Path) {
+        self.camera_path_file = path.to_path_buf();
+    }
+
+    /// Drives the camera along `path` from the next tick until it ends.
+    pub fn play_camera_path(&mut self, path: CameraPath, looping: bool) {
+        info!(
+            "Playing camera path: {} keyframes, {:.1}s",
+            path.keyframes().len(),
+            path.duration()
+        );
+        let mut player = CameraPathPlayer::new(path);
+        player.looping = looping;
+        self.path_player = Some(player);
+    }
+
+    pub fn stop_camera_path(&mut self) {
+        self.path_player = None;
+    }
+
+    #[allow(dead_code)]
+    pub fn camera_path_player(&self) -> Option<&CameraPathPlayer> {
+        self.path_player.as_ref()
+    }
+
+    fn add_camera_keyframe(&mut self) {
+        if self.camera_path.is_empty() {
+            self.camera_path_clock = 0f64;
+        }
+        let keyframe = Keyframe::from_camera(&self.camera, self.camera_path_clock as f32);
+        self.camera_path.add_keyframe(keyframe);
+        info!(
+            "Camera keyframe {} at {:.2}s",
+            self.camera_path.keyframes().len(),
+            keyframe.time
+        );
+    }
+
+    fn save_camera_path(&self) {
+        match self.camera_path.save(&self.camera_path_file) {
+            Ok(()) => info!(
+                "Saved {} camera keyframes to {}",
+                self.camera_path.keyframes().len(),
+                self.camera_path_file.display()
+            ),
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    fn advance_camera_path(&mut self, dt: f64) {
+        self.camera_path_clock += dt;
+        if let Some(player) = &mut self.path_player {
+            player.update(&mut self.camera, dt);
+            if player.is_finished() {
+                info!("Camera path finished");
+                self.path_player = None;
+            }
+        }
+    }
+
+    /// Runs `app` until the window is asked to close. The GLFW callbacks queue events into the
+    /// boxed event queue, so the engine itself may move.
+    pub fn run(&mut self, app: Box<dyn Application>) {
+        self.start(app);
+
+        self.window.show();
+        while !self.window.should_close() {
+            self.update_delta_time();
+            // Measured before a replay substitutes the recorded frame time.
+            let frame_time = self.delta_time;
+            self.record_frame();
+
+            let dt = self.timestep.tick;
+            for _ in 0..self.timestep.advance(self.delta_time) {
+                self.tick(dt, true);
+            }
+            self.alpha = self.timestep.alpha();
+
+            let alpha = self.alpha;
+            let (width, height) = self.window.framebuffer_size();
+            if let Some(watcher) = &mut self.shader_watcher {
+                watcher.poll(unsafe { glfwGetTime() });
+            }
+            if let Some(target) = &self.scene_target {
+                target.bind();
+            }
+            self.stats.begin_pass("render");
+            self.render_scene(alpha, width, height);
+            self.stats.end_pass();
+            if let Some(target) = &self.scene_target {
+                target.blit_to_default(width, height);
+            }
+            self.stats_overlay.draw(&self.stats, width, height);
+            if let Some(watcher) = &self.shader_watcher {
+                self.error_overlay.draw(watcher.errors(), width, height);
+            }
+
+            let cpu_time = unsafe { glfwGetTime() } - self.last_frame;
+            self.window.swap_buffers();
+            self.stats.end_frame(frame_time, cpu_time);
+            unsafe { glfwPollEvents() };
+            self.process_events();
+            self.replay_events();
+        }
+        self.stop();
+        self.input_mode = InputMode::Live;
+    }
+
+    /// Renders the main camera and every viewport in order, each clipped to its rectangle of a
+    /// `width` x `height` framebuffer.
+    fn render_scene(&mut self, alpha: f32, width: i32, height: i32) {
+        let mut views: Vec<(i32, Option<usize>)> = self
+            .viewports
+            .iter()
+            .enumerate()
+            .map(|(i, viewport)| (viewport.order, Some(i)))
+            .collect();
+        views.push((0, None));
+        // The main camera goes before other views of the same order.
+        views.sort_by_key(|&(order, view)| (order, view.is_some()));
+
+        let split = !self.viewports.is_empty() || self.main_rect != ViewportRect::FULL;
+        if split {
+            unsafe { gl::Enable(gl::SCISSOR_TEST) };
+        }
+        for (_, view) in views {
+            let rect = match view {
+                Some(i) => self.viewports[i].rect,
+                None => self.main_rect,
+            };
+            let (x, y, w, h) = rect.pixels(width, height);
+            if w <= 0 || h <= 0 {
+                continue;
+            }
+            unsafe {
+                gl::Viewport(x, y, w, h);
+                gl::Scissor(x, y, w, h);
+            }
+            self.active_view = view;
+            self.with_app(|app, engine| app.render(engine, alpha));
+
+            if let Some(viewport) = self.active_viewport()
+                && viewport.show_main_frustum
+            {
+                let camera = self.render_camera();
+                let main = self.camera.interpolate(&self.previous_camera, alpha);
+                let debug_draw = self.debug_draw.get_or_insert_with(DebugDraw::new);
+                if let Some(watcher) = &mut self.shader_watcher {
+                    debug_draw.reload_shader(watcher);
+                }
+                debug_draw.frustum(&main, &glm::vec4(1f32, 0.8, 0.2, 1f32));
+                debug_draw.draw(&camera);
+            }
+        }
+        self.active_view = None;
+        unsafe {
+            if split {
+                gl::Disable(gl::SCISSOR_TEST);
+            }
+            gl::Viewport(0, 0, width, height);
+        }
+    }
+
+    /// Records the frame time, or replaces it with the recorded one.
+    fn record_frame(&mut self) {
+        match &mut self.input_mode {
+            InputMode::Live => {}
+            InputMode::Recording(recorder) => recorder.record(&Record::Frame(self.delta_time)),
+            InputMode::Replaying(replay) => match replay.next_frame() {
+                Some(dt) => self.delta_time = dt,
+                None => {
+                    info!("Input replay finished, switching to live input");
+                    self.input_mode = InputMode::Live;
+                    self.input.reset_cursor();
+                }
+            },
+        }
+    }
+
+    fn replay_events(&mut self) {
+        let InputMode::Replaying(replay) = &mut self.input_mode else {
+            return;
+        };
+        for record in replay.next_events() {
+            if let Record::Input(event) = record {
+                self.dispatch(event);
+            }
+        }
+    }
+
+    /// Handles everything the callbacks queued during `glfwPollEvents`. Live keyboard and
+    /// mouse events are written to the recording, or dropped while replaying.
+    fn process_events(&mut self) {
+        self.frame_events.clear();
+        loop {
+            let Some(event) = self.events.borrow_mut().pop() else {
+                break;
+            };
+            if event.is_input() {
+                match &mut self.input_mode {
+                    InputMode::Live => {}
+                    InputMode::Recording(recorder) => {
+                        recorder.record(&Record::Input(event.clone()));
+                    }
+                    InputMode::Replaying(_) => continue,
+                }
+            }
+            self.dispatch(event);
+        }
+    }
+
+    /// Events handled since the last poll, oldest first.
+    #[allow(dead_code)]
+    pub fn events(&self) -> &[Event] {
+        &self.frame_events
+    }
+
+    fn start(&mut self, app: Box<dyn Application>) {
+        self.app = Some(app);
+        unsafe {
+            let events: *const RefCell<EventQueue> = &*self.events;
+            glfwSetWindowUserPointer(self.window.handle(), events as *mut c_void);
+        }
+        self.with_app(|app, engine| app.init(engine));
+    }
+
+    fn stop(&mut self) {
+        unsafe {
+            glfwSetWindowUserPointer(self.window.handle(), ptr::null_mut());
+        }
+        self.app = None;
+    }
+
+    fn tick(&mut self, dt: f64, read_input: bool) {
+        self.previous_camera = self.camera;
+        if read_input {
+            self.process_input(dt);
+        }
+        self.advance_camera_path(dt);
+        for viewport in &mut self.viewports {
+            viewport.previous_camera = viewport.camera;
+            if viewport.follow_main {
+                viewport.camera.set_target(self.camera.get_position());
+            }
+            viewport.camera.update(dt);
+        }
+        self.with_app(|app, engine| app.update(engine, dt));
+    }
+
+    fn with_app(&mut self, f: impl FnOnce(&mut dyn Application, &mut Engine)) {
+        if let Some(mut app) = self.app.take() {
+            f(app.as_mut(), self);
+            self.app = Some(app);
+        }
+    }
+
+    fn dispatch(&mut self, event: Event) {
+        match event {
+            Event::Resized(width, height) => {
+                self.window.on_framebuffer_resized(width, height);
+                self.resize_scene_target(width, height);
+                self.update_aspect_ratios();
+            }
+            Event::ContentScaleChanged(x, y) => self.window.on_content_scale_changed(x, y),
+            Event::CursorMoved(xpos, ypos)
+                if unsafe { glfwGetInputMode(self.window.handle(), CURSOR) } == CURSOR_DISABLED =>
+            {
+                self.input.on_cursor(xpos, ypos);
+            }
+            Event::Scroll(xoffset, yoffset) => self.input.on_scroll(xoffset, yoffset),
+            Event::GamepadConnected(jid) if self.gamepad.is_none() => {
+                let pad = gamepad::GlfwGamepad { jid };
+                info!("Using gamepad {}", pad.name());
+                self.gamepad = Some(Box::new(pad));
+            }
+            _ => {}
+        }
+        self.with_app(|app, engine| app.on_event(engine, &event));
+        self.frame_events.push(event);
+    }
+
+    fn poll_gamepad(&mut self) -> Option<GamepadState> {
+        for (jid, connected) in gamepad::take_connection_events() {
+            self.dispatch(if connected {
+                Event::GamepadConnected(jid)
+            } else {
+                Event::GamepadDisconnected(jid)
+            });
+        }
+        let state = self.gamepad.as_mut().and_then(|pad| pad.poll());
+        if state.is_none() && self.gamepad.is_some() {
+            info!("Gamepad disconnected");
+            self.gamepad =
+                gamepad::first_connected().map(|pad| Box::new(pad) as Box<dyn GamepadDevice>);
+        }
+        state
+    }
+
+    fn process_input(&mut self, dt: f64) {
+        let window = self.window.handle();
+        let tick = match &mut self.input_mode {
+            InputMode::Replaying(replay) => replay.next_tick(),
+            _ => {
+                let gamepad = self.poll_gamepad();
+                TickInput::capture(&self.input, &window, gamepad)
+            }
+        };
+        if let InputMode::Recording(recorder) = &mut self.input_mode {
+            recorder.record(&Record::Tick(tick.clone()));
+        }
+        self.input.set_gamepad(tick.gamepad);
+        self.input.update(&tick);
+        let input = &self.input;
+        unsafe {
+            if input.pressed("quit") {
+                glfwSetWindowShouldClose(window, TRUE)
+            }
+            if input.pressed("toggle_fullscreen") {
+                self.window.toggle_fullscreen();
+            }
+            if input.pressed("toggle_stats") {
+                self.stats_overlay.visible = !self.stats_overlay.visible;
+            }
+
+            let cursor = glfwGetInputMode(window, CURSOR);
+            if input.pressed("release_cursor")
+                || (input.pressed("toggle_cursor") && cursor == CURSOR_DISABLED)
+            {
+                glfwSetInputMode(window, CURSOR, CURSOR_NORMAL);
+                self.input.reset_cursor();
+            } else if input.pressed("capture_cursor")
+                || (input.pressed("toggle_cursor") && cursor != CURSOR_DISABLED)
+            {
+                glfwSetInputMode(window, CURSOR, CURSOR_DISABLED);
+            }
+        }
+
+        if self.input.pressed("toggle_minimap") {
+            self.toggle_minimap();
+        }
+        if self.input.pressed("path_keyframe") {
+            self.add_camera_keyframe();
+        }
+        if self.input.pressed("path_save") {
+            self.save_camera_path();
+        }
+        if self.input.pressed("path_play") {
+            if self.path_player.is_some() {
+                self.stop_camera_path();
+            } else if !self.camera_path.is_empty() {
+                self.play_camera_path(self.camera_path.clone(), false);
+            }
+        }
+
+        let input = &self.input;
+        if input.pressed("toggle_orbit") {
+            self.camera.toggle_mode();
+        }
+        if input.pressed("toggle_flight") {
+            self.camera.toggle_free_rotation();
+        }
+        if input.pressed("toggle_projection") {
+            self.camera.toggle_projection();
+        }
+        let presets = [
+            ("view_top", ViewPreset::Top),
+            ("view_front", ViewPreset::Front),
+            ("view_side", ViewPreset::Side),
+        ];
+        for (action, preset) in presets {
+            if input.pressed(action) {
+                self.camera.set_view_preset(preset);
+            }
+        }
+        self.camera.set_speed_mode(if input.held("sprint") {
+            SpeedMode::Sprint
+        } else if input.held("slow") {
+            SpeedMode::Slow
+        } else {
+            SpeedMode::Normal
+        });
+        self.camera.process_movement(
+            input.value("move_forward"),
+            input.value("move_right"),
+            input.value("move_up"),
+            dt,
+        );
+        self.camera.process_roll(input.value("roll"), dt);
+        let (yaw, pitch) = (input.value("look_yaw"), input.value("look_pitch"));
+        if yaw != 0f32 || pitch != 0f32 {
+            if input.held("orbit_pan") && self.camera.mode() == CameraMode::Orbit {
+                self.camera.process_pan(yaw, pitch);
+            } else {
+                self.camera.process_mouse(yaw, pitch);
+            }
+        }
+        if input.held("zoom") {
+            self.camera.process_scroll(input.value("zoom"));
+        }
+        self.camera.update(dt);
+    }
+
+    fn update_delta_time(&mut self) {
+        let currentframe = unsafe { glfwGetTime() };
+        self.delta_time = currentframe - self.last_frame;
+        self.last_frame = currentframe;
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        // Fields that delete GL objects have to go while the context still exists.
+        self.app = None;
+        self.debug_draw = None;
+        self.scene_target = None;
+        self.stats.release();
+        self.stats_overlay.release();
+        self.error_overlay.release();
+        self.window.destroy();
+        unsafe { glfwTerminate() };
+    }
+}
@@ -0,0 +1,106 @@
+#![allow(non_snake_case)]
+use nalgebra_glm as glm;
+
+use crate::camera::Camera;
+use crate::shader::{Shader, ShaderConstructor, ShaderWatcher};
+use crate::{as_c_void, sizeof};
+
+/// Floats per vertex: position (3) and RGBA color (4).
+const VERTEX_SIZE: usize = 7;
+
+/// World space lines drawn over the scene, batched until [`DebugDraw::draw`].
+pub struct DebugDraw {
+    shader: Shader,
+    VAO: u32,
+    VBO: u32,
+    vertices: Vec<f32>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        let shader = ShaderConstructor("shaders/debug_lines.vert", "shaders/debug_lines.frag");
+        let (mut VAO, mut VBO) = (0, 0);
+        unsafe {
+            gl::GenVertexArrays(1, &mut VAO);
+            gl::GenBuffers(1, &mut VBO);
+            gl::BindVertexArray(VAO);
+            gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
+            let stride = VERTEX_SIZE as i32 * sizeof!(f32);
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, std::ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                1,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * sizeof!(f32)) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+            gl::BindVertexArray(0);
+        }
+        Self {
+            shader,
+            VAO,
+            VBO,
+            vertices: Vec::new(),
+        }
+    }
+
+    pub fn reload_shader(&mut self, watcher: &mut ShaderWatcher) {
+        watcher.reload(&mut self.shader);
+    }
+
+    pub fn line(&mut self, a: &glm::Vec3, b: &glm::Vec3, color: &glm::Vec4) {
+        for p in [a, b] {
+            self.vertices
+                .extend_from_slice(&[p.x, p.y, p.z, color.x, color.y, color.z, color.w]);
+        }
+    }
+
+    /// The edges of `camera`'s view volume, cut off at its far plane.
+    pub fn frustum(&mut self, camera: &Camera, color: &glm::Vec4) {
+        let corners = camera.frustum_corners();
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.line(&corners[i], &corners[next], color);
+            self.line(&corners[i + 4], &corners[next + 4], color);
+            self.line(&corners[i], &corners[i + 4], color);
+        }
+    }
+
+    /// Draws everything queued since the last call as seen by `camera`.
+    pub fn draw(&mut self, camera: &Camera) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        unsafe {
+            self.shader.activate();
+            self.shader
+                .setMat4("view", camera.get_view_matrix(), gl::FALSE);
+            self.shader
+                .setMat4("projection", camera.get_projection_matrix(), gl::FALSE);
+
+            gl::BindVertexArray(self.VAO);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.VBO);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (self.vertices.len() * size_of::<f32>()) as isize,
+                as_c_void!(self.vertices),
+                gl::STREAM_DRAW,
+            );
+            gl::DrawArrays(gl::LINES, 0, (self.vertices.len() / VERTEX_SIZE) as i32);
+            gl::BindVertexArray(0);
+        }
+        self.vertices.clear();
+    }
+}
+
+impl Drop for DebugDraw {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.VAO);
+            gl::DeleteBuffers(1, &self.VBO);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use gl::types::GLenum;
+use glfw::ffi::glfwExtensionSupported;
+use log::{info, warn};
+
+use crate::camera::DepthMode;
+use crate::cstr;
+
+/// How the scene's depth buffer is set up, decided once the context exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthSetup {
+    pub mode: DepthMode,
+    /// Depth/stencil format for offscreen scene targets.
+    pub format: GLenum,
+}
+
+/// `glClipControl` is core in GL 4.5 and otherwise needs `GL_ARB_clip_control`.
+pub fn clip_control_supported() -> bool {
+    let (mut major, mut minor) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+    let extension = unsafe { glfwExtensionSupported(cstr!("GL_ARB_clip_control").as_ptr()) } != 0;
+    gl::ClipControl::is_loaded() && ((major, minor) >= (4, 5) || extension)
+}
+
+/// Sets the clip range, depth test and clear value for `reverse_z` on the current context,
+/// falling back to the standard depth range when the context can't do it.
+pub(super) fn configure(reverse_z: bool) -> DepthSetup {
+    if reverse_z && clip_control_supported() {
+        unsafe {
+            gl::ClipControl(gl::LOWER_LEFT, gl::ZERO_TO_ONE);
+            gl::DepthFunc(gl::GREATER);
+            gl::ClearDepth(0f64);
+        }
+        info!("Using a reverse-Z float depth buffer");
+        return DepthSetup {
+            mode: DepthMode::ReverseZ,
+            format: gl::DEPTH32F_STENCIL8,
+        };
+    }
+    if reverse_z {
+        warn!("glClipControl is not available, using a standard depth buffer");
+    }
+    unsafe {
+        gl::DepthFunc(gl::LESS);
+        gl::ClearDepth(1f64);
+    }
+    DepthSetup {
+        mode: DepthMode::Standard,
+        format: gl::DEPTH24_STENCIL8,
+    }
+}
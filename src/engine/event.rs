@@ -0,0 +1,212 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_uint};
+use std::path::PathBuf;
+
+use glfw::ffi::*;
+
+/// Everything GLFW reports about the window and its input, queued by the callbacks in this
+/// module and handed to the application once per frame by [`super::Engine::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    KeyPressed {
+        key: i32,
+        scancode: i32,
+        mods: i32,
+    },
+    KeyReleased {
+        key: i32,
+        scancode: i32,
+        mods: i32,
+    },
+    KeyRepeat {
+        key: i32,
+        scancode: i32,
+        mods: i32,
+    },
+    /// A Unicode character typed, after keyboard layout and dead keys are applied.
+    Char(char),
+    MouseButton {
+        button: i32,
+        pressed: bool,
+        mods: i32,
+    },
+    CursorMoved(f64, f64),
+    CursorEntered(bool),
+    Scroll(f64, f64),
+    /// New framebuffer size in pixels.
+    Resized(i32, i32),
+    Moved(i32, i32),
+    ContentScaleChanged(f32, f32),
+    FocusChanged(bool),
+    Iconified(bool),
+    Maximized(bool),
+    FileDropped(Vec<PathBuf>),
+    /// The user asked to close the window; clear [`crate::window::Window::set_should_close`]
+    /// to keep it open.
+    Closed,
+    GamepadConnected(i32),
+    GamepadDisconnected(i32),
+}
+
+impl Event {
+    /// Whether this comes from the keyboard or mouse, which replays substitute with recorded input.
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self,
+            Event::KeyPressed { .. }
+                | Event::KeyReleased { .. }
+                | Event::KeyRepeat { .. }
+                | Event::Char(_)
+                | Event::MouseButton { .. }
+                | Event::CursorMoved(..)
+                | Event::Scroll(..)
+        )
+    }
+}
+
+/// Events waiting to be handled, oldest first.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    events: VecDeque<Event>,
+}
+
+impl EventQueue {
+    pub fn push(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    pub fn pop(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
+
+/// Registers every window callback; they only queue events, the engine handles them after
+/// `glfwPollEvents` returns.
+pub(super) fn install_callbacks(window: *mut GLFWwindow) {
+    unsafe {
+        glfwSetFramebufferSizeCallback(window, Some(framebuffer_size_callback));
+        glfwSetWindowContentScaleCallback(window, Some(content_scale_callback));
+        glfwSetWindowPosCallback(window, Some(window_pos_callback));
+        glfwSetWindowFocusCallback(window, Some(focus_callback));
+        glfwSetWindowIconifyCallback(window, Some(iconify_callback));
+        glfwSetWindowMaximizeCallback(window, Some(maximize_callback));
+        glfwSetWindowCloseCallback(window, Some(close_callback));
+        glfwSetDropCallback(window, Some(drop_callback));
+        glfwSetKeyCallback(window, Some(key_callback));
+        glfwSetCharCallback(window, Some(char_callback));
+        glfwSetMouseButtonCallback(window, Some(mouse_button_callback));
+        glfwSetCursorPosCallback(window, Some(mouse_callback));
+        glfwSetCursorEnterCallback(window, Some(cursor_enter_callback));
+        glfwSetScrollCallback(window, Some(scroll_callback));
+    }
+}
+
+/// Queues `event` on the [`EventQueue`] the window's user pointer points at.
+fn push(window: *mut GLFWwindow, event: Event) {
+    let queue =
+        unsafe { (glfwGetWindowUserPointer(window) as *const RefCell<EventQueue>).as_ref() };
+    if let Some(mut queue) = queue.and_then(|q| q.try_borrow_mut().ok()) {
+        queue.push(event);
+    }
+}
+
+extern "C" fn framebuffer_size_callback(window: *mut GLFWwindow, width: c_int, height: c_int) {
+    push(window, Event::Resized(width, height));
+}
+
+extern "C" fn content_scale_callback(window: *mut GLFWwindow, xscale: f32, yscale: f32) {
+    push(window, Event::ContentScaleChanged(xscale, yscale));
+}
+
+extern "C" fn window_pos_callback(window: *mut GLFWwindow, x: c_int, y: c_int) {
+    push(window, Event::Moved(x, y));
+}
+
+extern "C" fn focus_callback(window: *mut GLFWwindow, focused: c_int) {
+    push(window, Event::FocusChanged(focused == TRUE));
+}
+
+extern "C" fn iconify_callback(window: *mut GLFWwindow, iconified: c_int) {
+    push(window, Event::Iconified(iconified == TRUE));
+}
+
+extern "C" fn maximize_callback(window: *mut GLFWwindow, maximized: c_int) {
+    push(window, Event::Maximized(maximized == TRUE));
+}
+
+extern "C" fn close_callback(window: *mut GLFWwindow) {
+    push(window, Event::Closed);
+}
+
+extern "C" fn drop_callback(window: *mut GLFWwindow, count: c_int, paths: *mut *const c_char) {
+    let paths = unsafe { std::slice::from_raw_parts(paths, count as usize) }
+        .iter()
+        .map(|&path| PathBuf::from(unsafe { CStr::from_ptr(path) }.to_string_lossy().as_ref()))
+        .collect();
+    push(window, Event::FileDropped(paths));
+}
+
+extern "C" fn key_callback(
+    window: *mut GLFWwindow,
+    key: c_int,
+    scancode: c_int,
+    action: c_int,
+    mods: c_int,
+) {
+    push(
+        window,
+        match action {
+            PRESS => Event::KeyPressed {
+                key,
+                scancode,
+                mods,
+            },
+            REPEAT => Event::KeyRepeat {
+                key,
+                scancode,
+                mods,
+            },
+            _ => Event::KeyReleased {
+                key,
+                scancode,
+                mods,
+            },
+        },
+    );
+}
+
+extern "C" fn char_callback(window: *mut GLFWwindow, codepoint: c_uint) {
+    if let Some(c) = char::from_u32(codepoint) {
+        push(window, Event::Char(c));
+    }
+}
+
+extern "C" fn mouse_button_callback(
+    window: *mut GLFWwindow,
+    button: c_int,
+    action: c_int,
+    mods: c_int,
+) {
+    push(
+        window,
+        Event::MouseButton {
+            button,
+            pressed: action == PRESS,
+            mods,
+        },
+    );
+}
+
+extern "C" fn mouse_callback(window: *mut GLFWwindow, xposIn: f64, yposIn: f64) {
+    push(window, Event::CursorMoved(xposIn, yposIn));
+}
+
+extern "C" fn cursor_enter_callback(window: *mut GLFWwindow, entered: c_int) {
+    push(window, Event::CursorEntered(entered == TRUE));
+}
+
+extern "C" fn scroll_callback(window: *mut GLFWwindow, xoffset: f64, yoffset: f64) {
+    push(window, Event::Scroll(xoffset, yoffset));
+}
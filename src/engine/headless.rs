@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+
+use log::{error, info};
+
+use super::{Application, Engine};
+use crate::framebuffer::Framebuffer;
+use crate::util::image::Image;
+
+/// Options for [`Engine::run_headless`].
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub frames: u32,
+    pub output: PathBuf,
+    /// Directory holding `frame_NNNN.png` images to compare each rendered frame against.
+    pub reference: Option<PathBuf>,
+    /// Largest per-channel difference that still counts as a matching pixel.
+    pub tolerance: u8,
+}
+
+impl HeadlessOptions {
+    pub fn new(frames: u32, output: PathBuf) -> Self {
+        Self {
+            frames,
+            output,
+            reference: None,
+            tolerance: 2,
+        }
+    }
+}
+
+impl Engine {
+    /// Renders `options.frames` frames of `app` into an offscreen framebuffer without ever showing
+    /// the window, and writes each one to `options.output` as `frame_NNNN.png`. Every frame
+    /// advances the simulation by exactly one tick so the output is reproducible.
+    ///
+    /// Returns the number of frames that did not match `options.reference`.
+    pub fn run_headless(
+        &mut self,
+        app: Box<dyn Application>,
+        options: &HeadlessOptions,
+    ) -> Result<u32, String> {
+        std::fs::create_dir_all(&options.output)
+            .map_err(|e| format!("Could not create {}: {}", options.output.display(), e))?;
+        let (width, height) = self.window.framebuffer_size();
+        let framebuffer = Framebuffer::with_format(width, height, self.depth.format, 0)?;
+
+        self.start(app);
+        framebuffer.bind();
+
+        let dt = self.timestep.tick;
+        let mut failures = 0;
+        for frame in 0..options.frames {
+            self.tick(dt, false);
+            self.alpha = 1f32;
+            let start = unsafe { glfw::ffi::glfwGetTime() };
+            self.stats.begin_pass("render");
+            self.render_scene(1f32, framebuffer.width, framebuffer.height);
+            self.stats.end_pass();
+            unsafe { gl::Finish() };
+            let elapsed = unsafe { glfw::ffi::glfwGetTime() } - start;
+            self.stats.end_frame(elapsed, elapsed);
+
+            let name = format!("frame_{:04}.png", frame);
+            let image = Image::new(
+                framebuffer.width as u32,
+                framebuffer.height as u32,
+                framebuffer.read_pixels(),
+            );
+            let path = options.output.join(&name);
+            image
+                .write_png(&path)
+                .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+
+            if let Some(reference) = &options.reference {
+                let expected = Image::load(&reference.join(&name))?;
+                let diff = image.diff(&expected, options.tolerance)?;
+                if diff.passed() {
+                    info!("{}: matches reference (max delta {})", name, diff.max_delta);
+                } else {
+                    error!(
+                        "{}: {}/{} pixels differ from reference (max delta {})",
+                        name, diff.mismatched_pixels, diff.total_pixels, diff.max_delta
+                    );
+                    failures += 1;
+                }
+            }
+        }
+
+        framebuffer.unbind();
+        self.stop();
+        info!("Headless stats: {}", self.stats.summary());
+        Ok(failures)
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use glfw::ffi::glfwExtensionSupported;
+use log::warn;
+
+use crate::cstr;
+use crate::shader::gl_version;
+
+/// Frames of CPU timing kept for the graph and the summary.
+pub const HISTORY_LENGTH: usize = 240;
+
+// Bumped from the draw code, which has no handle to the engine.
+static DRAW_CALLS: AtomicU32 = AtomicU32::new(0);
+static TRIANGLES: AtomicU64 = AtomicU64::new(0);
+static TEXTURE_BINDS: AtomicU32 = AtomicU32::new(0);
+static CULLED_MESHES: AtomicU32 = AtomicU32::new(0);
+
+/// Counts one indexed draw of `index_count` triangle-list indices.
+pub fn count_draw(index_count: usize) {
+    DRAW_CALLS.fetch_add(1, Ordering::Relaxed);
+    TRIANGLES.fetch_add(index_count as u64 / 3, Ordering::Relaxed);
+}
+
+pub fn count_texture_bind() {
+    TEXTURE_BINDS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts meshes skipped by frustum culling.
+pub fn count_culled(meshes: usize) {
+    CULLED_MESHES.fetch_add(meshes as u32, Ordering::Relaxed);
+}
+
+/// What one frame cost.
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    /// Wall-clock time since the previous frame, in milliseconds.
+    pub frame_time: f32,
+    /// Time spent updating and submitting the frame before swapping buffers, in milliseconds.
+    pub cpu_time: f32,
+    pub draw_calls: u32,
+    pub triangles: u64,
+    pub texture_binds: u32,
+    pub culled_meshes: u32,
+    /// GPU duration of each timed pass in milliseconds, from the most recent query that finished
+    /// (usually a frame or two behind).
+    pub gpu_passes: Vec<(String, f32)>,
+}
+
+/// Averages over the frame history, for logging benchmark runs.
+#[derive(Debug, Clone, Default)]
+pub struct StatsSummary {
+    pub frames: usize,
+    pub average_frame_time: f32,
+    pub min_frame_time: f32,
+    pub max_frame_time: f32,
+    pub average_cpu_time: f32,
+    pub draw_calls: u32,
+    pub triangles: u64,
+    pub texture_binds: u32,
+    pub culled_meshes: u32,
+    pub gpu_passes: Vec<(String, f32)>,
+}
+
+impl fmt::Display for StatsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} frames, frame {:.2} ms (min {:.2}, max {:.2}), cpu {:.2} ms, {} draws, {} triangles, {} texture binds, {} culled",
+            self.frames,
+            self.average_frame_time,
+            self.min_frame_time,
+            self.max_frame_time,
+            self.average_cpu_time,
+            self.draw_calls,
+            self.triangles,
+            self.texture_binds,
+            self.culled_meshes
+        )?;
+        for (name, ms) in &self.gpu_passes {
+            write!(f, ", gpu {} {:.2} ms", name, ms)?;
+        }
+        Ok(())
+    }
+}
+
+/// `GL_TIME_ELAPSED` is core in GL 3.3 and otherwise needs `GL_ARB_timer_query`.
+fn timer_query_supported() -> bool {
+    let extension = unsafe { glfwExtensionSupported(cstr!("GL_ARB_timer_query").as_ptr()) } != 0;
+    gl::GetQueryObjectui64v::is_loaded() && (gl_version() >= (3, 3) || extension)
+}
+
+/// `GL_TIME_ELAPSED` queries for named passes. Results are read without stalling: each pass
+/// keeps its in-flight queries and only collects the ones the GPU has finished.
+#[derive(Debug, Default)]
+struct GpuTimer {
+    pending: Vec<(String, u32)>,
+    free: Vec<u32>,
+    active: Option<(String, u32)>,
+    latest: Vec<(String, f32)>,
+    /// Whether the context has timer queries, checked on the first pass.
+    supported: Option<bool>,
+}
+
+impl GpuTimer {
+    fn begin(&mut self, name: &str) {
+        let supported = *self.supported.get_or_insert_with(|| {
+            let supported = timer_query_supported();
+            if !supported {
+                warn!("GPU pass timing needs GL 3.3 or GL_ARB_timer_query, skipping it");
+            }
+            supported
+        });
+        if !supported {
+            return;
+        }
+        if self.active.is_some() {
+            // GL_TIME_ELAPSED queries can't nest.
+            self.end();
+        }
+        let query = self.free.pop().unwrap_or_else(|| {
+            let mut query = 0;
+            unsafe { gl::GenQueries(1, &mut query) };
+            query
+        });
+        unsafe { gl::BeginQuery(gl::TIME_ELAPSED, query) };
+        self.active = Some((name.to_string(), query));
+    }
+
+    fn end(&mut self) {
+        if let Some(active) = self.active.take() {
+            unsafe { gl::EndQuery(gl::TIME_ELAPSED) };
+            self.pending.push(active);
+        }
+    }
+
+    fn collect(&mut self) {
+        let mut still_pending = Vec::new();
+        for (name, query) in self.pending.drain(..) {
+            let mut available = 0;
+            unsafe { gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available) };
+            if available == 0 {
+                still_pending.push((name, query));
+                continue;
+            }
+            let mut nanoseconds = 0u64;
+            unsafe { gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut nanoseconds) };
+            let ms = nanoseconds as f32 / 1_000_000f32;
+            match self.latest.iter_mut().find(|(n, _)| *n == name) {
+                Some(entry) => entry.1 = ms,
+                None => self.latest.push((name, ms)),
+            }
+            self.free.push(query);
+        }
+        self.pending = still_pending;
+    }
+
+    /// Deletes every query, open or not.
+    fn release(&mut self) {
+        let queries: Vec<u32> = self
+            .free
+            .drain(..)
+            .chain(self.pending.drain(..).map(|(_, q)| q))
+            .chain(self.active.take().map(|(_, q)| q))
+            .collect();
+        if !queries.is_empty() {
+            unsafe { gl::DeleteQueries(queries.len() as i32, queries.as_ptr()) };
+        }
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Per-frame CPU and GPU statistics, collected by the engine and shown by
+/// [`crate::gui::StatsOverlay`].
+#[derive(Debug, Default)]
+pub struct Stats {
+    history: VecDeque<FrameStats>,
+    gpu: GpuTimer,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts timing a GPU pass; ends the previous one if it is still open.
+    pub fn begin_pass(&mut self, name: &str) {
+        self.gpu.begin(name);
+    }
+
+    pub fn end_pass(&mut self) {
+        self.gpu.end();
+    }
+
+    /// Deletes the GPU queries, before the context goes away.
+    pub fn release(&mut self) {
+        self.gpu.release();
+    }
+
+    /// Closes the frame: takes the draw counters, collects finished GPU queries and adds the
+    /// frame to the history. Times are in seconds.
+    pub fn end_frame(&mut self, frame_time: f64, cpu_time: f64) {
+        self.gpu.end();
+        self.gpu.collect();
+        let frame = FrameStats {
+            frame_time: (frame_time * 1000f64) as f32,
+            cpu_time: (cpu_time * 1000f64) as f32,
+            draw_calls: DRAW_CALLS.swap(0, Ordering::Relaxed),
+            triangles: TRIANGLES.swap(0, Ordering::Relaxed),
+            texture_binds: TEXTURE_BINDS.swap(0, Ordering::Relaxed),
+            culled_meshes: CULLED_MESHES.swap(0, Ordering::Relaxed),
+            gpu_passes: self.gpu.latest.clone(),
+        };
+        self.history.push_back(frame);
+        if self.history.len() > HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+    }
+
+    /// The most recently completed frame.
+    pub fn last(&self) -> Option<&FrameStats> {
+        self.history.back()
+    }
+
+    /// Frame times in milliseconds, oldest first.
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.history.iter().map(|f| f.frame_time)
+    }
+
+    pub fn summary(&self) -> StatsSummary {
+        let frames = self.history.len();
+        if frames == 0 {
+            return StatsSummary::default();
+        }
+        let last = self.history.back().unwrap();
+        StatsSummary {
+            frames,
+            average_frame_time: self.frame_times().sum::<f32>() / frames as f32,
+            min_frame_time: self.frame_times().fold(f32::INFINITY, f32::min),
+            max_frame_time: self.frame_times().fold(0f32, f32::max),
+            average_cpu_time: self.history.iter().map(|f| f.cpu_time).sum::<f32>() / frames as f32,
+            draw_calls: last.draw_calls,
+            triangles: last.triangles,
+            texture_binds: last.texture_binds,
+            culled_meshes: last.culled_meshes,
+            gpu_passes: last.gpu_passes.clone(),
+        }
+    }
+}
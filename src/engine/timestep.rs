@@ -0,0 +1,86 @@
+/// Fixed-rate simulation clock. Frame time is accumulated and consumed in whole ticks, and the
+/// leftover fraction is exposed as the interpolation alpha for rendering.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimestep {
+    pub tick: f64,
+    pub max_steps: u32,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    /// `tick_rate` must be positive and `max_steps` at least 1, which loading the settings checks.
+    pub fn new(tick_rate: f64, max_steps: u32) -> Self {
+        Self {
+            tick: 1f64 / tick_rate,
+            max_steps,
+            accumulator: 0f64,
+        }
+    }
+
+    /// Adds `frame_time` to the accumulator and returns how many ticks should run this frame.
+    /// Anything beyond `max_steps` ticks is dropped so a long stall can't spiral.
+    pub fn advance(&mut self, frame_time: f64) -> u32 {
+        self.accumulator += frame_time.max(0f64);
+        let mut steps = (self.accumulator / self.tick) as u32;
+        if steps > self.max_steps {
+            steps = self.max_steps;
+            self.accumulator = 0f64;
+        } else {
+            self.accumulator -= steps as f64 * self.tick;
+        }
+        steps
+    }
+
+    /// How far the next tick has progressed, in `[0, 1)`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.tick) as f32
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(60f64, 5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn counts_whole_ticks() {
+        let mut timestep = FixedTimestep::new(10f64, 5);
+        assert_eq!(timestep.advance(0.05), 0);
+        assert_eq!(timestep.advance(0.05), 1);
+        assert_eq!(timestep.advance(0.25), 2);
+    }
+
+    #[test]
+    fn alpha_is_the_leftover_fraction() {
+        let mut timestep = FixedTimestep::new(10f64, 5);
+        assert_eq!(timestep.advance(0.125), 1);
+        assert!(approx(timestep.alpha(), 0.25));
+        assert_eq!(timestep.advance(0.05), 0);
+        assert!(approx(timestep.alpha(), 0.75));
+    }
+
+    #[test]
+    fn clamps_to_max_steps_and_drops_the_rest() {
+        let mut timestep = FixedTimestep::new(10f64, 3);
+        assert_eq!(timestep.advance(1.05), 3);
+        assert_eq!(timestep.alpha(), 0f32);
+        assert_eq!(timestep.advance(0.1), 1);
+    }
+
+    #[test]
+    fn ignores_negative_frame_times() {
+        let mut timestep = FixedTimestep::new(10f64, 5);
+        timestep.advance(0.05);
+        assert_eq!(timestep.advance(-1f64), 0);
+        assert!(approx(timestep.alpha(), 0.5));
+    }
+}
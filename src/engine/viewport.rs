@@ -0,0 +1,72 @@
+use crate::camera::Camera;
+
+/// Part of the framebuffer in 0..1 coordinates, origin bottom left like `glViewport`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewportRect {
+    pub const FULL: ViewportRect = ViewportRect::new(0f32, 0f32, 1f32, 1f32);
+    #[allow(dead_code)]
+    pub const LEFT_HALF: ViewportRect = ViewportRect::new(0f32, 0f32, 0.5, 1f32);
+    #[allow(dead_code)]
+    pub const RIGHT_HALF: ViewportRect = ViewportRect::new(0.5, 0f32, 0.5, 1f32);
+
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// `x, y, width, height` in pixels of a `width` x `height` framebuffer.
+    pub fn pixels(&self, width: i32, height: i32) -> (i32, i32, i32, i32) {
+        let x = (self.x * width as f32).round() as i32;
+        let y = (self.y * height as f32).round() as i32;
+        let right = ((self.x + self.width) * width as f32).round() as i32;
+        let top = ((self.y + self.height) * height as f32).round() as i32;
+        (x, y, right - x, top - y)
+    }
+
+    /// Aspect ratio of this rectangle in a `width` x `height` framebuffer, `None` when empty.
+    pub fn aspect_ratio(&self, width: i32, height: i32) -> Option<f32> {
+        let (_, _, w, h) = self.pixels(width, height);
+        (w > 0 && h > 0).then(|| w as f32 / h as f32)
+    }
+}
+
+/// An extra camera drawn into part of the window after (or before) the main one, for split
+/// screen, picture-in-picture or debug views.
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    pub name: String,
+    pub camera: Camera,
+    pub(super) previous_camera: Camera,
+    pub rect: ViewportRect,
+    /// Viewports are drawn in ascending order, the main camera has order 0.
+    pub order: i32,
+    /// Draw the main camera's frustum into this view.
+    pub show_main_frustum: bool,
+    /// Keep an orbiting camera's target on the main camera.
+    pub follow_main: bool,
+}
+
+impl Viewport {
+    pub fn new(name: &str, camera: Camera, rect: ViewportRect, order: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            camera,
+            previous_camera: camera,
+            rect,
+            order,
+            show_main_frustum: false,
+            follow_main: false,
+        }
+    }
+}
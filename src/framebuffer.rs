@@ -0,0 +1,154 @@
+#![allow(non_snake_case, dead_code)]
+use gl::types::{GLenum, GLuint};
+use log::debug;
+
+/// An offscreen render target with an RGBA8 color attachment and a depth/stencil renderbuffer.
+pub struct Framebuffer {
+    ID: GLuint,
+    color: GLuint,
+    depth: GLuint,
+    pub width: i32,
+    pub height: i32,
+    pub samples: i32,
+}
+
+impl Framebuffer {
+    pub fn new(width: i32, height: i32) -> Result<Self, String> {
+        Self::with_format(width, height, gl::DEPTH24_STENCIL8, 0)
+    }
+
+    /// `depth_format` is a combined depth/stencil format such as `DEPTH32F_STENCIL8`; with
+    /// `samples` above 0 the buffers are multisampled and must be resolved with
+    /// [`Framebuffer::blit_to_default`] before reading.
+    pub fn with_format(
+        width: i32,
+        height: i32,
+        depth_format: GLenum,
+        samples: i32,
+    ) -> Result<Self, String> {
+        unsafe {
+            let mut fb = Self {
+                ID: 0,
+                color: 0,
+                depth: 0,
+                width,
+                height,
+                samples,
+            };
+            let storage = |format: GLenum| {
+                if samples > 0 {
+                    gl::RenderbufferStorageMultisample(
+                        gl::RENDERBUFFER,
+                        samples,
+                        format,
+                        width,
+                        height,
+                    );
+                } else {
+                    gl::RenderbufferStorage(gl::RENDERBUFFER, format, width, height);
+                }
+            };
+            gl::GenFramebuffers(1, &mut fb.ID);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fb.ID);
+
+            gl::GenRenderbuffers(1, &mut fb.color);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, fb.color);
+            storage(gl::RGBA8);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                fb.color,
+            );
+
+            gl::GenRenderbuffers(1, &mut fb.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, fb.depth);
+            storage(depth_format);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                fb.depth,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Framebuffer is not complete: {:#x}", status));
+            }
+            debug!("Created {}x{} framebuffer {}", width, height, fb.ID);
+            Ok(fb)
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.ID);
+            gl::Viewport(0, 0, self.width, self.height);
+        }
+    }
+
+    pub fn unbind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Copies the color attachment to the window's framebuffer, resolving multisampling.
+    pub fn blit_to_default(&self, width: i32, height: i32) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.ID);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                self.width,
+                self.height,
+                0,
+                0,
+                width,
+                height,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Reads the color attachment back as tightly packed RGBA rows, top row first.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let row = self.width as usize * 4;
+        let mut pixels = vec![0u8; row * self.height as usize];
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.ID);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                self.width,
+                self.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        // GL's origin is the bottom-left corner, images are stored top-down.
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for line in pixels.chunks_exact(row).rev() {
+            flipped.extend_from_slice(line);
+        }
+        flipped
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.color);
+            gl::DeleteRenderbuffers(1, &self.depth);
+            gl::DeleteFramebuffers(1, &self.ID);
+        }
+    }
+}
@@ -1,5 +1,10 @@
+mod error_overlay;
 mod font;
-pub use font::Font;
+mod overlay;
+mod stats_overlay;
+pub use error_overlay::ErrorOverlay;
+pub use font::TTF;
+pub use stats_overlay::StatsOverlay;
 
 use gl;
 use glfw::ffi::{self, GLFWwindow};
@@ -16,16 +21,16 @@ impl GUI {
 
 pub struct TextLabel {
     bound_gui: GUI,
-    font: Font,
+    font: TTF,
 }
 
 impl TextLabel {
-    pub fn new(gui: GUI, font: Option<Font>) -> Self {
+    pub fn new(gui: GUI, font: Option<TTF>) -> Self {
         Self {
             bound_gui: gui,
             font: match font {
                 Some(n) => n,
-                None => Font::default(),
+                None => TTF::default(),
             },
         }
     }
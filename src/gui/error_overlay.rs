@@ -0,0 +1,70 @@
+use nalgebra_glm as glm;
+
+use super::font::bitmap::GLYPH_WIDTH;
+use super::overlay::Overlay;
+
+const SCALE: f32 = 2f32;
+const MARGIN: f32 = 8f32;
+/// Lines shown per message, the rest is only in the log.
+const MAX_LINES: usize = 12;
+
+/// Errors drawn along the bottom of the window, such as shader compile logs during hot-reload.
+#[derive(Default)]
+pub struct ErrorOverlay {
+    overlay: Option<Overlay>,
+}
+
+impl ErrorOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deletes the overlay's GL objects; it is recreated on the next draw.
+    pub fn release(&mut self) {
+        self.overlay = None;
+    }
+
+    /// Draws each `(title, message)` pair, nothing when `errors` is empty.
+    pub fn draw<'a>(
+        &mut self,
+        errors: impl Iterator<Item = (&'a str, &'a str)>,
+        width: i32,
+        height: i32,
+    ) {
+        let line_height = Overlay::line_height(SCALE);
+        let columns =
+            ((width as f32 - MARGIN * 4f32) / ((GLYPH_WIDTH + 1) as f32 * SCALE)).max(1f32);
+        let mut lines = Vec::new();
+        for (title, message) in errors {
+            lines.push((title.to_string(), glm::vec4(1f32, 0.4, 0.4, 1f32)));
+            for line in message
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .take(MAX_LINES)
+            {
+                let line: String = line.chars().take(columns as usize).collect();
+                lines.push((line, glm::vec4(1f32, 1f32, 1f32, 1f32)));
+            }
+        }
+        if lines.is_empty() {
+            return;
+        }
+        let overlay = self.overlay.get_or_insert_with(Overlay::new);
+
+        let panel_height = MARGIN * 2f32 + lines.len() as f32 * line_height;
+        let top = height as f32 - MARGIN - panel_height;
+        overlay.rect(
+            MARGIN,
+            top,
+            width as f32 - MARGIN * 2f32,
+            panel_height,
+            glm::vec4(0.2, 0f32, 0f32, 0.8),
+        );
+        let mut y = top + MARGIN;
+        for (line, color) in &lines {
+            overlay.text(MARGIN * 2f32, y, SCALE, line, *color);
+            y += line_height;
+        }
+        overlay.draw(width, height);
+    }
+}
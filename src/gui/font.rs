@@ -1,2 +1,3 @@
+pub mod bitmap;
 mod ttf;
 pub use ttf::TTF;
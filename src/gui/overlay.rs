@@ -0,0 +1,169 @@
+#![allow(non_snake_case)]
+use nalgebra_glm as glm;
+
+use super::font::bitmap::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::shader::{Shader, ShaderConstructor};
+use crate::{as_c_void, sizeof};
+
+/// Floats per vertex: position (2) and RGBA color (4).
+const VERTEX_SIZE: usize = 6;
+
+/// Immediate-mode 2D drawing in screen pixels, origin top left. Shapes are batched and submitted
+/// in one draw call by [`Overlay::draw`], on top of whatever is already in the framebuffer.
+pub struct Overlay {
+    shader: Shader,
+    VAO: u32,
+    VBO: u32,
+    vertices: Vec<f32>,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        let shader = ShaderConstructor("shaders/overlay.vert", "shaders/overlay.frag");
+        let (mut VAO, mut VBO) = (0, 0);
+        unsafe {
+            gl::GenVertexArrays(1, &mut VAO);
+            gl::GenBuffers(1, &mut VBO);
+            gl::BindVertexArray(VAO);
+            gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
+            let stride = VERTEX_SIZE as i32 * sizeof!(f32);
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, std::ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                1,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (2 * sizeof!(f32)) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+            gl::BindVertexArray(0);
+        }
+        Self {
+            shader,
+            VAO,
+            VBO,
+            vertices: Vec::new(),
+        }
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: glm::Vec4) {
+        let corners = [
+            (x, y),
+            (x, y + h),
+            (x + w, y + h),
+            (x, y),
+            (x + w, y + h),
+            (x + w, y),
+        ];
+        for (px, py) in corners {
+            self.vertices
+                .extend_from_slice(&[px, py, color.x, color.y, color.z, color.w]);
+        }
+    }
+
+    /// Draws `text` with the built-in bitmap font, each font pixel `scale` screen pixels
+    /// wide. Returns the width drawn.
+    pub fn text(&mut self, x: f32, y: f32, scale: f32, text: &str, color: glm::Vec4) -> f32 {
+        let advance = (GLYPH_WIDTH + 1) as f32 * scale;
+        let mut pen = x;
+        for c in text.chars() {
+            if let Some(rows) = bitmap::glyph(c) {
+                for (row, bits) in rows.iter().enumerate() {
+                    for column in 0..GLYPH_WIDTH {
+                        if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                            self.rect(
+                                pen + column as f32 * scale,
+                                y + row as f32 * scale,
+                                scale,
+                                scale,
+                                color,
+                            );
+                        }
+                    }
+                }
+            }
+            pen += advance;
+        }
+        pen - x
+    }
+
+    /// Height of one line of text at `scale`, including spacing.
+    pub fn line_height(scale: f32) -> f32 {
+        (GLYPH_HEIGHT + 2) as f32 * scale
+    }
+
+    /// A bar graph of `values` filling `w` x `h`, where `max` reaches the top.
+    #[allow(clippy::too_many_arguments)]
+    pub fn graph(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        values: &[f32],
+        max: f32,
+        color: glm::Vec4,
+    ) {
+        if values.is_empty() || max <= 0f32 {
+            return;
+        }
+        let bar = w / values.len() as f32;
+        for (i, value) in values.iter().enumerate() {
+            let height = (value / max).min(1f32) * h;
+            self.rect(x + i as f32 * bar, y + h - height, bar, height, color);
+        }
+    }
+
+    /// Submits everything queued since the last call for a `width` x `height` framebuffer.
+    pub fn draw(&mut self, width: i32, height: i32) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        unsafe {
+            let depth_test = gl::IsEnabled(gl::DEPTH_TEST);
+            let cull_face = gl::IsEnabled(gl::CULL_FACE);
+            let blend = gl::IsEnabled(gl::BLEND);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::CULL_FACE);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            self.shader.activate();
+            let projection = glm::ortho(0f32, width as f32, height as f32, 0f32, -1f32, 1f32);
+            self.shader.setMat4("projection", projection, gl::FALSE);
+
+            gl::BindVertexArray(self.VAO);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.VBO);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (self.vertices.len() * size_of::<f32>()) as isize,
+                as_c_void!(self.vertices),
+                gl::STREAM_DRAW,
+            );
+            gl::DrawArrays(gl::TRIANGLES, 0, (self.vertices.len() / VERTEX_SIZE) as i32);
+            gl::BindVertexArray(0);
+
+            if depth_test == gl::TRUE {
+                gl::Enable(gl::DEPTH_TEST);
+            }
+            if cull_face == gl::TRUE {
+                gl::Enable(gl::CULL_FACE);
+            }
+            if blend == gl::FALSE {
+                gl::Disable(gl::BLEND);
+            }
+        }
+        self.vertices.clear();
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.VAO);
+            gl::DeleteBuffers(1, &self.VBO);
+        }
+    }
+}
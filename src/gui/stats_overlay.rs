@@ -0,0 +1,101 @@
+use nalgebra_glm as glm;
+
+use super::overlay::Overlay;
+use crate::engine::stats::{HISTORY_LENGTH, Stats};
+
+const SCALE: f32 = 2f32;
+const MARGIN: f32 = 8f32;
+const PANEL_WIDTH: f32 = 360f32;
+const GRAPH_HEIGHT: f32 = 60f32;
+/// Frame time at the top of the graph, in milliseconds.
+const GRAPH_MAX: f32 = 50f32;
+const TARGET_FRAME_TIME: f32 = 1000f32 / 60f32;
+
+/// Frame time graph and counters drawn in the top left corner, toggled with `toggle_stats`.
+#[derive(Default)]
+pub struct StatsOverlay {
+    // Created on first draw, so the engine can hold one before any shader can be compiled.
+    overlay: Option<Overlay>,
+    pub visible: bool,
+}
+
+impl StatsOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deletes the overlay's GL objects; it is recreated on the next draw.
+    pub fn release(&mut self) {
+        self.overlay = None;
+    }
+
+    pub fn draw(&mut self, stats: &Stats, width: i32, height: i32) {
+        if !self.visible {
+            return;
+        }
+        let Some(frame) = stats.last() else {
+            return;
+        };
+        let overlay = self.overlay.get_or_insert_with(Overlay::new);
+
+        let text_color = glm::vec4(1f32, 1f32, 1f32, 1f32);
+        let mut lines = vec![
+            format!(
+                "FPS {:.0}  FRAME {:.2} MS",
+                1000f32 / frame.frame_time.max(0.001),
+                frame.frame_time
+            ),
+            format!("CPU {:.2} MS", frame.cpu_time),
+            format!(
+                "DRAWS {}  TRIS {}  BINDS {}",
+                frame.draw_calls, frame.triangles, frame.texture_binds
+            ),
+            format!("CULLED {}", frame.culled_meshes),
+        ];
+        for (name, ms) in &frame.gpu_passes {
+            lines.push(format!("GPU {} {:.2} MS", name, ms));
+        }
+
+        let line_height = Overlay::line_height(SCALE);
+        let panel_height = MARGIN * 3f32 + lines.len() as f32 * line_height + GRAPH_HEIGHT;
+        overlay.rect(
+            MARGIN,
+            MARGIN,
+            PANEL_WIDTH,
+            panel_height,
+            glm::vec4(0f32, 0f32, 0f32, 0.6),
+        );
+
+        let x = MARGIN * 2f32;
+        let mut y = MARGIN * 2f32;
+        for line in &lines {
+            overlay.text(x, y, SCALE, line, text_color);
+            y += line_height;
+        }
+
+        let graph_width = PANEL_WIDTH - MARGIN * 2f32;
+        let bar_width = graph_width / HISTORY_LENGTH as f32;
+        let times: Vec<f32> = stats.frame_times().collect();
+        // Newest frame on the right edge.
+        let graph_x = x + graph_width - times.len() as f32 * bar_width;
+        overlay.graph(
+            graph_x,
+            y,
+            times.len() as f32 * bar_width,
+            GRAPH_HEIGHT,
+            &times,
+            GRAPH_MAX,
+            glm::vec4(0.3, 0.9, 0.3, 0.9),
+        );
+        let target_y = y + GRAPH_HEIGHT * (1f32 - TARGET_FRAME_TIME / GRAPH_MAX);
+        overlay.rect(
+            x,
+            target_y,
+            graph_width,
+            1f32,
+            glm::vec4(1f32, 0.3, 0.3, 0.9),
+        );
+
+        overlay.draw(width, height);
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Read;
+
+use glfw::ffi::*;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_management::get_asset;
+
+pub mod gamepad;
+pub mod recording;
+use gamepad::{GamepadConfig, GamepadState};
+
+const DEFAULT_BINDINGS: &str = include_str!("assets/config/input.yml");
+
+const NAMED_KEYS: [(&str, i32); 46] = [
+    ("SPACE", KEY_SPACE),
+    ("APOSTROPHE", KEY_APOSTROPHE),
+    ("COMMA", KEY_COMMA),
+    ("MINUS", KEY_MINUS),
+    ("PERIOD", KEY_PERIOD),
+    ("SLASH", KEY_SLASH),
+    ("SEMICOLON", KEY_SEMICOLON),
+    ("EQUAL", KEY_EQUAL),
+    ("LEFT_BRACKET", KEY_LEFT_BRACKET),
+    ("BACKSLASH", KEY_BACKSLASH),
+    ("RIGHT_BRACKET", KEY_RIGHT_BRACKET),
+    ("GRAVE_ACCENT", KEY_GRAVE_ACCENT),
+    ("ESCAPE", KEY_ESCAPE),
+    ("ENTER", KEY_ENTER),
+    ("TAB", KEY_TAB),
+    ("BACKSPACE", KEY_BACKSPACE),
+    ("INSERT", KEY_INSERT),
+    ("DELETE", KEY_DELETE),
+    ("RIGHT", KEY_RIGHT),
+    ("LEFT", KEY_LEFT),
+    ("DOWN", KEY_DOWN),
+    ("UP", KEY_UP),
+    ("PAGE_UP", KEY_PAGE_UP),
+    ("PAGE_DOWN", KEY_PAGE_DOWN),
+    ("HOME", KEY_HOME),
+    ("END", KEY_END),
+    ("CAPS_LOCK", KEY_CAPS_LOCK),
+    ("SCROLL_LOCK", KEY_SCROLL_LOCK),
+    ("NUM_LOCK", KEY_NUM_LOCK),
+    ("PRINT_SCREEN", KEY_PRINT_SCREEN),
+    ("PAUSE", KEY_PAUSE),
+    ("KP_DECIMAL", KEY_KP_DECIMAL),
+    ("KP_DIVIDE", KEY_KP_DIVIDE),
+    ("KP_MULTIPLY", KEY_KP_MULTIPLY),
+    ("KP_SUBTRACT", KEY_KP_SUBTRACT),
+    ("KP_ADD", KEY_KP_ADD),
+    ("KP_ENTER", KEY_KP_ENTER),
+    ("KP_EQUAL", KEY_KP_EQUAL),
+    ("LEFT_SHIFT", KEY_LEFT_SHIFT),
+    ("LEFT_CONTROL", KEY_LEFT_CONTROL),
+    ("LEFT_ALT", KEY_LEFT_ALT),
+    ("LEFT_SUPER", KEY_LEFT_SUPER),
+    ("RIGHT_SHIFT", KEY_RIGHT_SHIFT),
+    ("RIGHT_CONTROL", KEY_RIGHT_CONTROL),
+    ("RIGHT_ALT", KEY_RIGHT_ALT),
+    ("RIGHT_SUPER", KEY_RIGHT_SUPER),
+];
+
+const MODIFIERS: [(&str, i32, [i32; 2]); 4] = [
+    ("SHIFT", MOD_SHIFT, [KEY_LEFT_SHIFT, KEY_RIGHT_SHIFT]),
+    ("CTRL", MOD_CONTROL, [KEY_LEFT_CONTROL, KEY_RIGHT_CONTROL]),
+    ("ALT", MOD_ALT, [KEY_LEFT_ALT, KEY_RIGHT_ALT]),
+    ("SUPER", MOD_SUPER, [KEY_LEFT_SUPER, KEY_RIGHT_SUPER]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// A single physical input a binding can be made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(i32),
+    MouseButton(i32),
+    /// Cursor movement since the last tick, in screen pixels.
+    MouseAxis(Axis),
+    /// Scroll wheel movement since the last tick.
+    Scroll(Axis),
+    /// Gamepad stick or trigger position after its dead zone and response curve.
+    GamepadAxis(i32),
+    GamepadButton(i32),
+}
+
+impl Input {
+    pub fn parse(name: &str) -> Option<Input> {
+        let name = name.trim().to_uppercase();
+        let bytes = name.as_bytes();
+        if bytes.len() == 1 && (bytes[0].is_ascii_uppercase() || bytes[0].is_ascii_digit()) {
+            // GLFW key codes for letters and digits are their ASCII values
+            return Some(Input::Key(bytes[0] as i32));
+        }
+        if let Some(n) = name.strip_prefix("KP_").and_then(|n| n.parse::<i32>().ok()) {
+            return (0..=9).contains(&n).then_some(Input::Key(KEY_KP_0 + n));
+        }
+        if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<i32>().ok()) {
+            return (1..=25).contains(&n).then_some(Input::Key(KEY_F1 + n - 1));
+        }
+        if let Some(n) = name
+            .strip_prefix("MOUSE_")
+            .and_then(|n| n.parse::<i32>().ok())
+        {
+            return (1..=8).contains(&n).then_some(Input::MouseButton(n - 1));
+        }
+        if let Some(pad) = name.strip_prefix("GAMEPAD_") {
+            if let Some((_, axis)) = gamepad::AXIS_NAMES.iter().find(|(n, _)| *n == pad) {
+                return Some(Input::GamepadAxis(*axis));
+            }
+            return gamepad::BUTTON_NAMES
+                .iter()
+                .find(|(n, _)| *n == pad)
+                .map(|(_, button)| Input::GamepadButton(*button));
+        }
+        match name.as_str() {
+            "MOUSE_LEFT" => Some(Input::MouseButton(MOUSE_BUTTON_LEFT)),
+            "MOUSE_RIGHT" => Some(Input::MouseButton(MOUSE_BUTTON_RIGHT)),
+            "MOUSE_MIDDLE" => Some(Input::MouseButton(MOUSE_BUTTON_MIDDLE)),
+            "MOUSE_X" => Some(Input::MouseAxis(Axis::X)),
+            "MOUSE_Y" => Some(Input::MouseAxis(Axis::Y)),
+            "SCROLL_X" => Some(Input::Scroll(Axis::X)),
+            "SCROLL_Y" => Some(Input::Scroll(Axis::Y)),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, key)| Input::Key(*key)),
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Input::Key(key) if (KEY_0..=KEY_9).contains(&key) || (KEY_A..=KEY_Z).contains(&key) => {
+                write!(f, "{}", key as u8 as char)
+            }
+            Input::Key(key) if (KEY_KP_0..=KEY_KP_9).contains(&key) => {
+                write!(f, "KP_{}", key - KEY_KP_0)
+            }
+            Input::Key(key) if (KEY_F1..=KEY_F25).contains(&key) => {
+                write!(f, "F{}", key - KEY_F1 + 1)
+            }
+            Input::Key(key) => match NAMED_KEYS.iter().find(|(_, k)| *k == key) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "UNKNOWN"),
+            },
+            Input::MouseButton(button) => write!(f, "MOUSE_{}", button + 1),
+            Input::MouseAxis(Axis::X) => write!(f, "MOUSE_X"),
+            Input::MouseAxis(Axis::Y) => write!(f, "MOUSE_Y"),
+            Input::Scroll(Axis::X) => write!(f, "SCROLL_X"),
+            Input::Scroll(Axis::Y) => write!(f, "SCROLL_Y"),
+            Input::GamepadAxis(axis) => {
+                write!(f, "GAMEPAD_{}", gamepad::AXIS_NAMES[axis as usize].0)
+            }
+            Input::GamepadButton(button) => {
+                write!(f, "GAMEPAD_{}", gamepad::BUTTON_NAMES[button as usize].0)
+            }
+        }
+    }
+}
+
+/// A chord of inputs that must all be active, plus modifiers that must be held.
+/// Written as `CTRL+SHIFT+S` or `Q+E`; axes in a chord contribute their movement as the value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub modifiers: i32,
+    pub inputs: Vec<Input>,
+    pub scale: f32,
+}
+
+impl Binding {
+    #[allow(dead_code)]
+    pub fn new(input: Input, scale: f32) -> Self {
+        Self {
+            modifiers: 0,
+            inputs: vec![input],
+            scale,
+        }
+    }
+
+    pub fn parse(chord: &str, scale: f32) -> Result<Self, String> {
+        let mut binding = Binding {
+            modifiers: 0,
+            inputs: Vec::new(),
+            scale,
+        };
+        for part in chord.split('+') {
+            let part = part.trim().to_uppercase();
+            if let Some((_, bit, _)) = MODIFIERS.iter().find(|(n, _, _)| *n == part) {
+                binding.modifiers |= bit;
+            } else {
+                binding
+                    .inputs
+                    .push(Input::parse(&part).ok_or(format!("Unknown input '{}'", part))?);
+            }
+        }
+        if binding.inputs.is_empty() {
+            return Err(format!("Binding '{}' has no inputs", chord));
+        }
+        Ok(binding)
+    }
+
+    fn chord(&self) -> String {
+        let mut parts: Vec<String> = MODIFIERS
+            .iter()
+            .filter(|(_, bit, _)| self.modifiers & bit != 0)
+            .map(|(name, _, _)| name.to_string())
+            .collect();
+        parts.extend(self.inputs.iter().map(|input| input.to_string()));
+        parts.join("+")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum BindingDef {
+    Chord(String),
+    Scaled { input: String, scale: f32 },
+}
+
+/// Polled state the bindings are evaluated against.
+pub trait InputSource {
+    fn key_down(&self, key: i32) -> bool;
+    fn button_down(&self, button: i32) -> bool;
+}
+
+impl InputSource for *mut GLFWwindow {
+    fn key_down(&self, key: i32) -> bool {
+        unsafe { glfwGetKey(*self, key) == PRESS }
+    }
+
+    fn button_down(&self, button: i32) -> bool {
+        unsafe { glfwGetMouseButton(*self, button) == PRESS }
+    }
+}
+
+/// Maps named actions ("move_forward", "look_yaw", ...) to keys, mouse buttons, mouse axes and
+/// the scroll wheel. Values are re-evaluated once per simulation tick by [`InputMap::update`].
+#[derive(Debug, Clone, Default)]
+pub struct InputMap {
+    bindings: BTreeMap<String, Vec<Binding>>,
+    values: HashMap<String, f32>,
+    previous: HashMap<String, f32>,
+    cursor: Option<(f64, f64)>,
+    mouse_delta: (f64, f64),
+    scroll_delta: (f64, f64),
+    gamepad: GamepadState,
+    pub gamepad_config: GamepadConfig,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::from_yaml(DEFAULT_BINDINGS).expect("Built-in input bindings are invalid")
+    }
+
+    /// Loads bindings from an asset, falling back to the built-in defaults if it is missing or invalid.
+    pub fn load(path: &str) -> Self {
+        let mut yaml = String::new();
+        let result = get_asset(path).and_then(|mut f| {
+            f.read_to_string(&mut yaml).map_err(|e| e.to_string())?;
+            Self::from_yaml(&yaml)
+        });
+        match result {
+            Ok(map) => map,
+            Err(e) => {
+                warn!("Using default input bindings: {}", e);
+                Self::new()
+            }
+        }
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, String> {
+        let defs: BTreeMap<String, Vec<BindingDef>> =
+            serde_yaml::from_str(yaml).map_err(|e| format!("Invalid input bindings: {}", e))?;
+        let mut map = InputMap::default();
+        for (action, defs) in defs {
+            let mut bindings = Vec::new();
+            for def in defs {
+                bindings.push(match def {
+                    BindingDef::Chord(chord) => Binding::parse(&chord, 1f32)?,
+                    BindingDef::Scaled { input, scale } => Binding::parse(&input, scale)?,
+                });
+            }
+            map.bindings.insert(action, bindings);
+        }
+        Ok(map)
+    }
+
+    #[allow(dead_code)]
+    pub fn to_yaml(&self) -> String {
+        let defs: BTreeMap<&String, Vec<BindingDef>> = self
+            .bindings
+            .iter()
+            .map(|(action, bindings)| {
+                let defs = bindings
+                    .iter()
+                    .map(|b| {
+                        if b.scale == 1f32 {
+                            BindingDef::Chord(b.chord())
+                        } else {
+                            BindingDef::Scaled {
+                                input: b.chord(),
+                                scale: b.scale,
+                            }
+                        }
+                    })
+                    .collect();
+                (action, defs)
+            })
+            .collect();
+        serde_yaml::to_string(&defs).unwrap()
+    }
+
+    #[allow(dead_code)]
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], |b| b.as_slice())
+    }
+
+    /// Adds another binding to `action`.
+    #[allow(dead_code)]
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        self.bindings
+            .entry(action.to_string())
+            .or_default()
+            .push(binding);
+    }
+
+    /// Replaces every binding of `action`.
+    #[allow(dead_code)]
+    pub fn rebind(&mut self, action: &str, bindings: Vec<Binding>) {
+        self.bindings.insert(action.to_string(), bindings);
+    }
+
+    #[allow(dead_code)]
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    pub fn on_cursor(&mut self, x: f64, y: f64) {
+        if let Some((last_x, last_y)) = self.cursor {
+            self.mouse_delta.0 += x - last_x;
+            self.mouse_delta.1 += y - last_y;
+        }
+        self.cursor = Some((x, y));
+    }
+
+    /// Forgets the last cursor position so the next movement doesn't jump.
+    pub fn reset_cursor(&mut self) {
+        self.cursor = None;
+        self.mouse_delta = (0f64, 0f64);
+    }
+
+    pub fn on_scroll(&mut self, x: f64, y: f64) {
+        self.scroll_delta.0 += x;
+        self.scroll_delta.1 += y;
+    }
+
+    /// Sets the gamepad state the next [`InputMap::update`] evaluates, `None` when there is no pad.
+    pub fn set_gamepad(&mut self, state: Option<GamepadState>) {
+        self.gamepad = match state {
+            Some(state) => self.gamepad_config.apply(&state),
+            None => GamepadState::default(),
+        };
+    }
+
+    /// Re-evaluates every action and consumes the accumulated mouse and scroll movement.
+    pub fn update(&mut self, source: &impl InputSource) {
+        self.previous = std::mem::take(&mut self.values);
+        for (action, bindings) in &self.bindings {
+            let value: f32 = bindings.iter().map(|b| self.evaluate(b, source)).sum();
+            self.values.insert(action.clone(), value);
+        }
+        self.mouse_delta = (0f64, 0f64);
+        self.scroll_delta = (0f64, 0f64);
+    }
+
+    fn evaluate(&self, binding: &Binding, source: &impl InputSource) -> f32 {
+        for (_, bit, keys) in MODIFIERS {
+            if binding.modifiers & bit != 0 && !keys.iter().any(|&k| source.key_down(k)) {
+                return 0f32;
+            }
+        }
+        let mut value = 1f32;
+        for input in &binding.inputs {
+            match *input {
+                Input::Key(key) if !source.key_down(key) => return 0f32,
+                Input::MouseButton(button) if !source.button_down(button) => return 0f32,
+                Input::MouseAxis(Axis::X) => value *= self.mouse_delta.0 as f32,
+                Input::MouseAxis(Axis::Y) => value *= self.mouse_delta.1 as f32,
+                Input::Scroll(Axis::X) => value *= self.scroll_delta.0 as f32,
+                Input::Scroll(Axis::Y) => value *= self.scroll_delta.1 as f32,
+                Input::GamepadAxis(axis) => value *= self.gamepad.axes[axis as usize],
+                Input::GamepadButton(button) if !self.gamepad.buttons[button as usize] => {
+                    return 0f32;
+                }
+                _ => {}
+            }
+        }
+        value * binding.scale
+    }
+
+    pub fn value(&self, action: &str) -> f32 {
+        self.values.get(action).copied().unwrap_or(0f32)
+    }
+
+    pub fn held(&self, action: &str) -> bool {
+        self.value(action) != 0f32
+    }
+
+    /// True on the tick the action became active.
+    pub fn pressed(&self, action: &str) -> bool {
+        self.held(action) && self.previous.get(action).copied().unwrap_or(0f32) == 0f32
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::{CStr, CString};
+use std::io::Read;
+use std::sync::Mutex;
+
+use glfw::ffi::*;
+use log::{debug, info, warn};
+use serde::Deserialize;
+
+use crate::asset_management::get_asset;
+
+pub const AXIS_COUNT: usize = (GAMEPAD_AXIS_LAST + 1) as usize;
+pub const BUTTON_COUNT: usize = (GAMEPAD_BUTTON_LAST + 1) as usize;
+
+pub(super) const AXIS_NAMES: [(&str, i32); AXIS_COUNT] = [
+    ("LEFT_X", GAMEPAD_AXIS_LEFT_X),
+    ("LEFT_Y", GAMEPAD_AXIS_LEFT_Y),
+    ("RIGHT_X", GAMEPAD_AXIS_RIGHT_X),
+    ("RIGHT_Y", GAMEPAD_AXIS_RIGHT_Y),
+    ("LEFT_TRIGGER", GAMEPAD_AXIS_LEFT_TRIGGER),
+    ("RIGHT_TRIGGER", GAMEPAD_AXIS_RIGHT_TRIGGER),
+];
+
+pub(super) const BUTTON_NAMES: [(&str, i32); BUTTON_COUNT] = [
+    ("A", GAMEPAD_BUTTON_A),
+    ("B", GAMEPAD_BUTTON_B),
+    ("X", GAMEPAD_BUTTON_X),
+    ("Y", GAMEPAD_BUTTON_Y),
+    ("LEFT_BUMPER", GAMEPAD_BUTTON_LEFT_BUMPER),
+    ("RIGHT_BUMPER", GAMEPAD_BUTTON_RIGHT_BUMPER),
+    ("BACK", GAMEPAD_BUTTON_BACK),
+    ("START", GAMEPAD_BUTTON_START),
+    ("GUIDE", GAMEPAD_BUTTON_GUIDE),
+    ("LEFT_THUMB", GAMEPAD_BUTTON_LEFT_THUMB),
+    ("RIGHT_THUMB", GAMEPAD_BUTTON_RIGHT_THUMB),
+    ("DPAD_UP", GAMEPAD_BUTTON_DPAD_UP),
+    ("DPAD_RIGHT", GAMEPAD_BUTTON_DPAD_RIGHT),
+    ("DPAD_DOWN", GAMEPAD_BUTTON_DPAD_DOWN),
+    ("DPAD_LEFT", GAMEPAD_BUTTON_DPAD_LEFT),
+];
+
+/// Connect/disconnect notifications from the GLFW joystick callback, which carries no user
+/// pointer, waiting to be drained by [`take_connection_events`].
+static CONNECTION_EVENTS: Mutex<Vec<(i32, bool)>> = Mutex::new(Vec::new());
+
+/// Raw axis and button values of a gamepad, laid out like `GLFWgamepadstate`. Sticks span
+/// -1..1 and triggers rest at -1; [`GamepadConfig::apply`] maps triggers to 0..1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GamepadState {
+    pub axes: [f32; AXIS_COUNT],
+    pub buttons: [bool; BUTTON_COUNT],
+}
+
+impl GamepadState {
+    /// What an untouched pad reports: centered sticks and released triggers.
+    pub const RESTING: GamepadState = GamepadState {
+        axes: [0f32, 0f32, 0f32, 0f32, -1f32, -1f32],
+        buttons: [false; BUTTON_COUNT],
+    };
+}
+
+fn is_trigger(axis: usize) -> bool {
+    axis == GAMEPAD_AXIS_LEFT_TRIGGER as usize || axis == GAMEPAD_AXIS_RIGHT_TRIGGER as usize
+}
+
+/// Anything that can be polled for a [`GamepadState`] once per tick.
+pub trait GamepadDevice {
+    fn name(&self) -> String;
+    /// Returns `None` once the device is gone.
+    fn poll(&mut self) -> Option<GamepadState>;
+}
+
+/// A joystick that GLFW recognises as a gamepad through its mapping database.
+pub struct GlfwGamepad {
+    pub jid: i32,
+}
+
+impl GamepadDevice for GlfwGamepad {
+    fn name(&self) -> String {
+        unsafe {
+            let name = glfwGetGamepadName(self.jid);
+            if name.is_null() {
+                format!("Joystick {}", self.jid)
+            } else {
+                CStr::from_ptr(name).to_string_lossy().into_owned()
+            }
+        }
+    }
+
+    fn poll(&mut self) -> Option<GamepadState> {
+        let mut raw = GLFWgamepadstate {
+            buttons: [0; BUTTON_COUNT],
+            axes: [0f32; AXIS_COUNT],
+        };
+        if unsafe { glfwGetGamepadState(self.jid, &mut raw) } == 0 {
+            return None;
+        }
+        Some(GamepadState {
+            axes: raw.axes,
+            buttons: raw.buttons.map(|b| b as i32 == PRESS),
+        })
+    }
+}
+
+/// Plays back recorded states one per poll and then keeps reporting the last one, so input
+/// handling can be exercised without hardware.
+pub struct MockGamepad {
+    states: VecDeque<GamepadState>,
+    last: GamepadState,
+}
+
+impl MockGamepad {
+    #[allow(dead_code)]
+    pub fn new(states: Vec<GamepadState>) -> Self {
+        Self {
+            states: states.into(),
+            last: GamepadState::RESTING,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn push(&mut self, state: GamepadState) {
+        self.states.push_back(state);
+    }
+}
+
+impl GamepadDevice for MockGamepad {
+    fn name(&self) -> String {
+        "Mock Gamepad".to_string()
+    }
+
+    fn poll(&mut self) -> Option<GamepadState> {
+        if let Some(state) = self.states.pop_front() {
+            self.last = state;
+        }
+        Some(self.last)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseCurve {
+    Linear,
+    Quadratic,
+    Cubic,
+    /// `|x|^n`, keeping the sign of `x`.
+    Power(f32),
+}
+
+/// Dead zone and response curve applied to one gamepad axis before it reaches the input map.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AxisConfig {
+    pub dead_zone: f32,
+    pub curve: ResponseCurve,
+    pub invert: bool,
+}
+
+impl Default for AxisConfig {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15f32,
+            curve: ResponseCurve::Quadratic,
+            invert: false,
+        }
+    }
+}
+
+impl AxisConfig {
+    /// Zeroes input inside the dead zone and rescales the rest so the output still spans 0..1.
+    pub fn apply(&self, raw: f32) -> f32 {
+        let magnitude = raw.abs().min(1f32);
+        if magnitude <= self.dead_zone {
+            return 0f32;
+        }
+        let t = (magnitude - self.dead_zone) / (1f32 - self.dead_zone);
+        let shaped = match self.curve {
+            ResponseCurve::Linear => t,
+            ResponseCurve::Quadratic => t * t,
+            ResponseCurve::Cubic => t * t * t,
+            ResponseCurve::Power(n) => t.powf(n),
+        };
+        let value = shaped.copysign(raw);
+        if self.invert { -value } else { value }
+    }
+}
+
+/// Per-axis settings, loaded from `config/gamepad.yml` (keys are axis names such as `LEFT_X`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GamepadConfig {
+    pub axes: [AxisConfig; AXIS_COUNT],
+}
+
+impl GamepadConfig {
+    pub fn load(path: &str) -> Self {
+        let mut yaml = String::new();
+        let result = get_asset(path).and_then(|mut f| {
+            f.read_to_string(&mut yaml).map_err(|e| e.to_string())?;
+            serde_yaml::from_str::<BTreeMap<String, AxisConfig>>(&yaml).map_err(|e| e.to_string())
+        });
+        let mut config = GamepadConfig::default();
+        match result {
+            Ok(axes) => {
+                for (name, axis) in axes {
+                    match AXIS_NAMES.iter().find(|(n, _)| *n == name.to_uppercase()) {
+                        Some((_, i)) => config.axes[*i as usize] = axis,
+                        None => warn!("Unknown gamepad axis '{}' in {}", name, path),
+                    }
+                }
+            }
+            Err(e) => debug!("Using default gamepad settings: {}", e),
+        }
+        config
+    }
+
+    /// Shapes every axis, with triggers first moved from -1..1 to 0..1 so they read 0 at rest.
+    pub fn apply(&self, state: &GamepadState) -> GamepadState {
+        let mut shaped = *state;
+        for (axis, (value, config)) in shaped.axes.iter_mut().zip(self.axes.iter()).enumerate() {
+            let raw = if is_trigger(axis) {
+                (*value + 1f32) / 2f32
+            } else {
+                *value
+            };
+            *value = config.apply(raw);
+        }
+        shaped
+    }
+}
+
+/// Installs the connect/disconnect callback and loads extra SDL-style mappings from
+/// `config/gamecontrollerdb.txt` if the asset exists.
+pub fn init_gamepads() {
+    unsafe {
+        glfwSetJoystickCallback(Some(joystick_callback));
+    }
+    let mut mappings = String::new();
+    let Ok(mut f) = get_asset("config/gamecontrollerdb.txt") else {
+        return;
+    };
+    if f.read_to_string(&mut mappings).is_ok() {
+        let mappings = CString::new(mappings).unwrap_or_default();
+        if unsafe { glfwUpdateGamepadMappings(mappings.as_ptr()) } == TRUE {
+            info!("Loaded gamepad mappings");
+        } else {
+            warn!("Failed to load gamepad mappings");
+        }
+    }
+}
+
+/// The first joystick GLFW currently recognises as a gamepad.
+pub fn first_connected() -> Option<GlfwGamepad> {
+    (JOYSTICK_1..=JOYSTICK_LAST)
+        .find(|&jid| unsafe { glfwJoystickIsGamepad(jid) } == TRUE)
+        .map(|jid| GlfwGamepad { jid })
+}
+
+/// Joystick ids and whether they connected (`true`) or disconnected since the last call.
+pub fn take_connection_events() -> Vec<(i32, bool)> {
+    std::mem::take(&mut *CONNECTION_EVENTS.lock().unwrap())
+}
+
+extern "C" fn joystick_callback(jid: i32, event: i32) {
+    CONNECTION_EVENTS
+        .lock()
+        .unwrap()
+        .push((jid, event == CONNECTED));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{InputMap, InputSource};
+
+    /// No keys or mouse buttons held.
+    struct Idle;
+
+    impl InputSource for Idle {
+        fn key_down(&self, _key: i32) -> bool {
+            false
+        }
+
+        fn button_down(&self, _button: i32) -> bool {
+            false
+        }
+    }
+
+    fn axis(dead_zone: f32, curve: ResponseCurve) -> AxisConfig {
+        AxisConfig {
+            dead_zone,
+            curve,
+            invert: false,
+        }
+    }
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    fn with_axis(axis: i32, value: f32) -> GamepadState {
+        let mut state = GamepadState::RESTING;
+        state.axes[axis as usize] = value;
+        state
+    }
+
+    #[test]
+    fn dead_zone_cuts_off_and_rescales() {
+        let config = axis(0.2, ResponseCurve::Linear);
+        assert_eq!(config.apply(0.1), 0f32);
+        assert_eq!(config.apply(-0.2), 0f32);
+        assert!(approx(config.apply(0.6), 0.5));
+        assert!(approx(config.apply(-0.6), -0.5));
+        assert!(approx(config.apply(1f32), 1f32));
+        assert!(approx(config.apply(1.5), 1f32));
+    }
+
+    #[test]
+    fn curves_shape_the_rescaled_value() {
+        let at_half = |curve| axis(0f32, curve).apply(0.5);
+        assert!(approx(at_half(ResponseCurve::Linear), 0.5));
+        assert!(approx(at_half(ResponseCurve::Quadratic), 0.25));
+        assert!(approx(at_half(ResponseCurve::Cubic), 0.125));
+        assert!(approx(at_half(ResponseCurve::Power(3f32)), 0.125));
+        assert!(approx(
+            axis(0f32, ResponseCurve::Quadratic).apply(-0.5),
+            -0.25
+        ));
+    }
+
+    #[test]
+    fn invert_flips_the_sign() {
+        let config = AxisConfig {
+            invert: true,
+            ..axis(0f32, ResponseCurve::Linear)
+        };
+        assert!(approx(config.apply(0.5), -0.5));
+        assert!(approx(config.apply(-1f32), 1f32));
+    }
+
+    #[test]
+    fn triggers_read_zero_at_rest() {
+        let config = GamepadConfig::default();
+        let mut pad = MockGamepad::new(vec![with_axis(GAMEPAD_AXIS_LEFT_TRIGGER, 1f32)]);
+
+        let pressed = config.apply(&pad.poll().unwrap());
+        assert!(approx(
+            pressed.axes[GAMEPAD_AXIS_LEFT_TRIGGER as usize],
+            1f32
+        ));
+        assert_eq!(pressed.axes[GAMEPAD_AXIS_RIGHT_TRIGGER as usize], 0f32);
+
+        let mut idle = MockGamepad::new(Vec::new());
+        let resting = config.apply(&idle.poll().unwrap());
+        assert_eq!(resting.axes, [0f32; AXIS_COUNT]);
+    }
+
+    #[test]
+    fn mock_gamepad_drives_the_input_map() {
+        let mut input = InputMap::from_yaml(
+            "zoom:\n  - GAMEPAD_RIGHT_TRIGGER\nmove_forward:\n  - { input: GAMEPAD_LEFT_Y, scale: -1 }\njump:\n  - GAMEPAD_A\n",
+        )
+        .unwrap();
+        input.gamepad_config.axes[GAMEPAD_AXIS_LEFT_Y as usize] = axis(0.1, ResponseCurve::Linear);
+        let mut pushed = with_axis(GAMEPAD_AXIS_LEFT_Y, -1f32);
+        pushed.buttons[GAMEPAD_BUTTON_A as usize] = true;
+        let mut pad = MockGamepad::new(vec![
+            GamepadState::RESTING,
+            with_axis(GAMEPAD_AXIS_LEFT_Y, 0.05),
+            pushed,
+        ]);
+
+        input.set_gamepad(pad.poll());
+        input.update(&Idle);
+        assert!(!input.held("zoom"));
+        assert!(!input.held("move_forward"));
+
+        input.set_gamepad(pad.poll());
+        input.update(&Idle);
+        assert!(!input.held("move_forward"), "inside the dead zone");
+
+        input.set_gamepad(pad.poll());
+        input.update(&Idle);
+        assert!(approx(input.value("move_forward"), 1f32));
+        assert!(input.pressed("jump"));
+
+        // The mock keeps reporting its last state.
+        input.set_gamepad(pad.poll());
+        input.update(&Idle);
+        assert!(input.held("jump") && !input.pressed("jump"));
+    }
+}
@@ -0,0 +1,528 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use log::{error, info};
+
+use super::gamepad::{BUTTON_COUNT, GamepadState};
+use super::{Input, InputMap, InputSource, MODIFIERS};
+use crate::engine::Event;
+use crate::util::{BinaryReader, BinaryWriter};
+
+const MAGIC: &str = "WPINPUT";
+/// Version 2 added key, mouse button and character records; version 1 files still load.
+const VERSION: u16 = 2;
+
+const TAG_FRAME: u8 = 0;
+const TAG_TICK: u8 = 1;
+const TAG_CURSOR: u8 = 2;
+const TAG_SCROLL: u8 = 3;
+const TAG_KEY: u8 = 4;
+const TAG_MOUSE_BUTTON: u8 = 5;
+const TAG_CHAR: u8 = 6;
+
+const KEY_PRESSED: u8 = 0;
+const KEY_RELEASED: u8 = 1;
+const KEY_REPEAT: u8 = 2;
+
+/// The polled input one simulation tick was evaluated against: the bound keys and mouse buttons
+/// that were down, and the raw gamepad state. Live ticks are captured into one of these too, so
+/// recorded and replayed runs go through exactly the same evaluation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TickInput {
+    pub keys: Vec<i32>,
+    pub buttons: Vec<i32>,
+    pub gamepad: Option<GamepadState>,
+}
+
+impl TickInput {
+    /// Samples every key and mouse button `map` has a binding for.
+    pub fn capture(
+        map: &InputMap,
+        source: &impl InputSource,
+        gamepad: Option<GamepadState>,
+    ) -> Self {
+        let mut tick = TickInput {
+            gamepad,
+            ..Default::default()
+        };
+        for binding in map.bindings.values().flatten() {
+            for (_, bit, keys) in MODIFIERS {
+                if binding.modifiers & bit != 0 {
+                    for key in keys {
+                        tick.sample_key(key, source);
+                    }
+                }
+            }
+            for input in &binding.inputs {
+                match *input {
+                    Input::Key(key) => tick.sample_key(key, source),
+                    Input::MouseButton(button)
+                        if source.button_down(button) && !tick.buttons.contains(&button) =>
+                    {
+                        tick.buttons.push(button);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        tick
+    }
+
+    fn sample_key(&mut self, key: i32, source: &impl InputSource) {
+        if source.key_down(key) && !self.keys.contains(&key) {
+            self.keys.push(key);
+        }
+    }
+
+    fn write(&self, w: &mut BinaryWriter) -> io::Result<()> {
+        w.put_uint8(self.keys.len() as u8)?;
+        for &key in &self.keys {
+            w.put_int16(key as i16)?;
+        }
+        w.put_uint8(self.buttons.len() as u8)?;
+        for &button in &self.buttons {
+            w.put_uint8(button as u8)?;
+        }
+        match &self.gamepad {
+            Some(state) => {
+                w.put_uint8(1)?;
+                for &axis in &state.axes {
+                    w.put_f32(axis)?;
+                }
+                let mut mask = 0u16;
+                for (i, &down) in state.buttons.iter().enumerate() {
+                    mask |= (down as u16) << i;
+                }
+                w.put_uint16(mask)
+            }
+            None => w.put_uint8(0),
+        }
+    }
+
+    fn read(r: &mut BinaryReader) -> Self {
+        let keys = (0..r.get_uint8()).map(|_| r.get_int16() as i32).collect();
+        let buttons = (0..r.get_uint8()).map(|_| r.get_uint8() as i32).collect();
+        let gamepad = (r.get_uint8() != 0).then(|| {
+            let mut state = GamepadState::default();
+            for axis in state.axes.iter_mut() {
+                *axis = r.get_f32();
+            }
+            let mask = r.get_uint16();
+            for (i, down) in state.buttons.iter_mut().enumerate() {
+                *down = mask & (1 << i) != 0;
+            }
+            state
+        });
+        TickInput {
+            keys,
+            buttons,
+            gamepad,
+        }
+    }
+}
+
+impl InputSource for TickInput {
+    fn key_down(&self, key: i32) -> bool {
+        self.keys.contains(&key)
+    }
+
+    fn button_down(&self, button: i32) -> bool {
+        self.buttons.contains(&button)
+    }
+}
+
+/// One entry of a recording, in the order the engine saw it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    /// Start of a rendered frame and the wall-clock time since the previous one.
+    Frame(f64),
+    Tick(TickInput),
+    /// A keyboard or mouse event, see [`Event::is_input`].
+    Input(Event),
+}
+
+/// Header fields a replay needs to step the simulation the same way the recording did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordingInfo {
+    pub tick: f64,
+    pub max_steps: u32,
+}
+
+/// Streams [`Record`]s to a file as they happen.
+///
+/// Layout (big-endian): `WPINPUT`, `u16` version, `f64` tick length, `u32` max steps, then one
+/// `u8` tag per record followed by its payload.
+pub struct InputRecorder {
+    writer: BinaryWriter,
+    records: usize,
+    /// Set after a failed write; later records are dropped instead of corrupting the file.
+    failed: bool,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path, info: RecordingInfo) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create recording {}: {}", path.display(), e))?;
+        let mut writer = BinaryWriter::new(file);
+        let header = (|| {
+            writer.put_string(MAGIC)?;
+            writer.put_uint16(VERSION)?;
+            writer.put_f64(info.tick)?;
+            writer.put_uint32(info.max_steps)
+        })();
+        header.map_err(|e| format!("Failed to write recording {}: {}", path.display(), e))?;
+        info!("Recording input to {}", path.display());
+        Ok(Self {
+            writer,
+            records: 0,
+            failed: false,
+        })
+    }
+
+    /// Appends `record`. The first write error is logged and ends the recording.
+    pub fn record(&mut self, record: &Record) {
+        if self.failed {
+            return;
+        }
+        match self.write(record) {
+            Ok(()) => self.records += 1,
+            Err(e) => {
+                error!(
+                    "Failed to write input record, stopping the recording after {} records: {}",
+                    self.records, e
+                );
+                self.failed = true;
+            }
+        }
+    }
+
+    fn write(&mut self, record: &Record) -> io::Result<()> {
+        let w = &mut self.writer;
+        match record {
+            Record::Frame(dt) => {
+                w.put_uint8(TAG_FRAME)?;
+                w.put_f64(*dt)
+            }
+            Record::Tick(tick) => {
+                w.put_uint8(TAG_TICK)?;
+                tick.write(w)
+            }
+            Record::Input(event) => write_event(w, event),
+        }
+    }
+}
+
+fn write_event(w: &mut BinaryWriter, event: &Event) -> io::Result<()> {
+    let (action, key, scancode, mods) = match *event {
+        Event::CursorMoved(x, y) => {
+            w.put_uint8(TAG_CURSOR)?;
+            w.put_f64(x)?;
+            return w.put_f64(y);
+        }
+        Event::Scroll(x, y) => {
+            w.put_uint8(TAG_SCROLL)?;
+            w.put_f64(x)?;
+            return w.put_f64(y);
+        }
+        Event::MouseButton {
+            button,
+            pressed,
+            mods,
+        } => {
+            w.put_uint8(TAG_MOUSE_BUTTON)?;
+            w.put_uint8(button as u8)?;
+            w.put_uint8(pressed as u8)?;
+            return w.put_uint8(mods as u8);
+        }
+        Event::Char(c) => {
+            w.put_uint8(TAG_CHAR)?;
+            return w.put_uint32(c as u32);
+        }
+        Event::KeyPressed {
+            key,
+            scancode,
+            mods,
+        } => (KEY_PRESSED, key, scancode, mods),
+        Event::KeyReleased {
+            key,
+            scancode,
+            mods,
+        } => (KEY_RELEASED, key, scancode, mods),
+        Event::KeyRepeat {
+            key,
+            scancode,
+            mods,
+        } => (KEY_REPEAT, key, scancode, mods),
+        // Only input events are recorded.
+        _ => return Ok(()),
+    };
+    w.put_uint8(TAG_KEY)?;
+    w.put_uint8(action)?;
+    w.put_int16(key as i16)?;
+    w.put_int32(scancode)?;
+    w.put_uint8(mods as u8)
+}
+
+fn read_key(r: &mut BinaryReader) -> Result<Event, String> {
+    let action = r.get_uint8();
+    let key = r.get_int16() as i32;
+    let scancode = r.get_int32();
+    let mods = r.get_uint8() as i32;
+    Ok(match action {
+        KEY_PRESSED => Event::KeyPressed {
+            key,
+            scancode,
+            mods,
+        },
+        KEY_RELEASED => Event::KeyReleased {
+            key,
+            scancode,
+            mods,
+        },
+        KEY_REPEAT => Event::KeyRepeat {
+            key,
+            scancode,
+            mods,
+        },
+        action => return Err(format!("Corrupt recording: unknown key action {}", action)),
+    })
+}
+
+impl Drop for InputRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            error!("Failed to finish the input recording: {}", e);
+        }
+        info!("Recorded {} input records", self.records);
+    }
+}
+
+/// A loaded recording, consumed in the same order it was written.
+pub struct InputReplay {
+    pub info: RecordingInfo,
+    records: VecDeque<Record>,
+}
+
+impl InputReplay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("Failed to open recording {}: {}", path.display(), e))?;
+        let mut r = BinaryReader::new(file);
+        let len = r.len();
+        if r.is_empty() || r.get_string(MAGIC.len()) != MAGIC {
+            return Err(format!("{} is not an input recording", path.display()));
+        }
+        let version = r.get_uint16();
+        if version == 0 || version > VERSION {
+            return Err(format!("Unsupported recording version {}", version));
+        }
+        let info = RecordingInfo {
+            tick: r.get_f64(),
+            max_steps: r.get_uint32(),
+        };
+        if !(info.tick.is_finite() && info.tick > 0f64) || info.max_steps == 0 {
+            return Err(format!(
+                "Corrupt recording: invalid timestep of {}s and {} steps",
+                info.tick, info.max_steps
+            ));
+        }
+        let mut records = VecDeque::new();
+        while r.get_position() < len {
+            records.push_back(match r.get_uint8() {
+                TAG_FRAME => Record::Frame(r.get_f64()),
+                TAG_TICK => Record::Tick(TickInput::read(&mut r)),
+                TAG_CURSOR => Record::Input(Event::CursorMoved(r.get_f64(), r.get_f64())),
+                TAG_SCROLL => Record::Input(Event::Scroll(r.get_f64(), r.get_f64())),
+                TAG_KEY => Record::Input(read_key(&mut r)?),
+                TAG_MOUSE_BUTTON => Record::Input(Event::MouseButton {
+                    button: r.get_uint8() as i32,
+                    pressed: r.get_uint8() != 0,
+                    mods: r.get_uint8() as i32,
+                }),
+                TAG_CHAR => match char::from_u32(r.get_uint32()) {
+                    Some(c) => Record::Input(Event::Char(c)),
+                    None => return Err("Corrupt recording: invalid character".to_string()),
+                },
+                tag => return Err(format!("Corrupt recording: unknown record tag {}", tag)),
+            });
+        }
+        info!(
+            "Loaded {} input records from {}",
+            records.len(),
+            path.display()
+        );
+        Ok(Self { info, records })
+    }
+
+    /// Frame time of the next frame, or `None` once the recording is exhausted.
+    pub fn next_frame(&mut self) -> Option<f64> {
+        match self.records.pop_front()? {
+            Record::Frame(dt) => Some(dt),
+            _ => None,
+        }
+    }
+
+    /// Polled input for the next tick; an empty tick if the recording ran out mid-frame.
+    pub fn next_tick(&mut self) -> TickInput {
+        if matches!(self.records.front(), Some(Record::Tick(_)))
+            && let Some(Record::Tick(tick)) = self.records.pop_front()
+        {
+            return tick;
+        }
+        TickInput::default()
+    }
+
+    /// Keyboard and mouse events that arrived after the current frame was rendered.
+    pub fn next_events(&mut self) -> Vec<Record> {
+        let mut events = Vec::new();
+        while let Some(record) = self.records.front() {
+            if matches!(record, Record::Frame(_) | Record::Tick(_)) {
+                break;
+            }
+            events.extend(self.records.pop_front());
+        }
+        events
+    }
+}
+
+// Gamepad buttons are packed into a u16 mask.
+const _: () = assert!(BUTTON_COUNT <= 16);
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use glfw::ffi::*;
+
+    use super::*;
+
+    const INFO: RecordingInfo = RecordingInfo {
+        tick: 1f64 / 60f64,
+        max_steps: 5,
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("recording-{}-{}.bin", name, std::process::id()))
+    }
+
+    /// Writes a header with `version` followed by `body`, as a recording from another build would.
+    fn write_raw(
+        path: &Path,
+        version: u16,
+        body: impl FnOnce(&mut BinaryWriter) -> io::Result<()>,
+    ) {
+        let mut w = BinaryWriter::new(File::create(path).unwrap());
+        w.put_string(MAGIC).unwrap();
+        w.put_uint16(version).unwrap();
+        w.put_f64(INFO.tick).unwrap();
+        w.put_uint32(INFO.max_steps).unwrap();
+        body(&mut w).unwrap();
+        w.flush().unwrap();
+    }
+
+    fn load(path: &Path) -> Result<InputReplay, String> {
+        let replay = InputReplay::load(path);
+        std::fs::remove_file(path).unwrap();
+        replay
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let mut gamepad = GamepadState::RESTING;
+        gamepad.axes[GAMEPAD_AXIS_LEFT_X as usize] = -0.5;
+        gamepad.axes[GAMEPAD_AXIS_RIGHT_TRIGGER as usize] = 0.25;
+        gamepad.buttons[GAMEPAD_BUTTON_A as usize] = true;
+        gamepad.buttons[GAMEPAD_BUTTON_DPAD_LEFT as usize] = true;
+        let records = vec![
+            Record::Frame(0.016),
+            Record::Tick(TickInput {
+                keys: vec![KEY_W, KEY_LEFT_SHIFT],
+                buttons: vec![MOUSE_BUTTON_LEFT, MOUSE_BUTTON_RIGHT],
+                gamepad: Some(gamepad),
+            }),
+            Record::Tick(TickInput::default()),
+            Record::Input(Event::CursorMoved(12.5, -3.0)),
+            Record::Input(Event::Scroll(0.0, 1.0)),
+            Record::Input(Event::KeyPressed {
+                key: KEY_ESCAPE,
+                scancode: 9,
+                mods: MOD_CONTROL,
+            }),
+            Record::Input(Event::KeyReleased {
+                key: KEY_UNKNOWN,
+                scancode: 300,
+                mods: 0,
+            }),
+            Record::Input(Event::KeyRepeat {
+                key: KEY_A,
+                scancode: 38,
+                mods: MOD_SHIFT | MOD_ALT,
+            }),
+            Record::Input(Event::MouseButton {
+                button: MOUSE_BUTTON_MIDDLE,
+                pressed: true,
+                mods: MOD_SUPER,
+            }),
+            Record::Input(Event::Char('é')),
+        ];
+
+        let path = temp_path("round-trip");
+        let mut recorder = InputRecorder::create(&path, INFO).unwrap();
+        for record in &records {
+            recorder.record(record);
+        }
+        drop(recorder);
+
+        let replay = load(&path).unwrap();
+        assert_eq!(replay.info, INFO);
+        assert_eq!(Vec::from(replay.records), records);
+    }
+
+    #[test]
+    fn version_1_recordings_load() {
+        let path = temp_path("version-1");
+        write_raw(&path, 1, |w| {
+            w.put_uint8(TAG_FRAME)?;
+            w.put_f64(0.02)?;
+            w.put_uint8(TAG_CURSOR)?;
+            w.put_f64(4.0)?;
+            w.put_f64(5.0)
+        });
+        let replay = load(&path).unwrap();
+        assert_eq!(
+            Vec::from(replay.records),
+            [
+                Record::Frame(0.02),
+                Record::Input(Event::CursorMoved(4.0, 5.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        for version in [0, VERSION + 1] {
+            let path = temp_path(&format!("version-{}", version));
+            write_raw(&path, version, |_| Ok(()));
+            assert!(load(&path).is_err(), "version {} loaded", version);
+        }
+    }
+
+    #[test]
+    fn unknown_tags_are_rejected() {
+        let path = temp_path("tag");
+        write_raw(&path, VERSION, |w| w.put_uint8(TAG_CHAR + 1));
+        assert!(load(&path).is_err());
+
+        let path = temp_path("key-action");
+        write_raw(&path, VERSION, |w| {
+            w.put_uint8(TAG_KEY)?;
+            w.put_uint8(KEY_REPEAT + 1)?;
+            w.put_int16(KEY_A as i16)?;
+            w.put_int32(0)?;
+            w.put_uint8(0)
+        });
+        assert!(load(&path).is_err());
+    }
+}
@@ -51,68 +51,6 @@ macro_rules! cstr_to_ptr_array {
     }};
 }
 #[macro_export]
-macro_rules! check_shader_compile {
-    ($shader:expr) => {{
-        use gl::types::{GLchar, GLint};
-        use std::ffi::CStr;
-        use std::ptr;
-
-        let mut success: GLint = 0;
-
-        gl::GetShaderiv($shader, gl::COMPILE_STATUS, &mut success);
-
-        if success == 0 {
-            let mut log_length: GLint = 0;
-
-            gl::GetShaderiv($shader, gl::INFO_LOG_LENGTH, &mut log_length);
-
-            if log_length > 0 {
-                let mut buffer = Vec::with_capacity(log_length as usize);
-                buffer.extend(std::iter::repeat(b' ' as i8).take(log_length as usize));
-                let error_ptr = buffer.as_mut_ptr() as *mut GLchar;
-
-                gl::GetShaderInfoLog($shader, log_length, ptr::null_mut(), error_ptr);
-
-                let c_str = CStr::from_ptr(error_ptr);
-                log::error!("Shader compilation failed:\n{}", c_str.to_string_lossy());
-            } else {
-                log::error!("Shader compilation failed:\n{}", "Unknown Error");
-            }
-        } else {
-            log::debug!("Shader compiled successfully");
-        }
-    }};
-}
-#[macro_export]
-macro_rules! check_program_link {
-    ($program:expr) => {{
-        use gl::types::{GLchar, GLint};
-        use std::ffi::CStr;
-        use std::ptr;
-
-        let mut success: GLint = 0;
-
-        gl::GetProgramiv($program, gl::LINK_STATUS, &mut success);
-
-        if success == 0 {
-            let mut log_length: GLint = 0;
-
-            gl::GetProgramiv($program, gl::INFO_LOG_LENGTH, &mut log_length);
-
-            let mut buffer: Vec<i8> = Vec::with_capacity(log_length as usize);
-            buffer.extend(std::iter::repeat(b' ' as i8).take(log_length as usize));
-            let error_ptr = buffer.as_mut_ptr() as *mut GLchar;
-
-            gl::GetProgramInfoLog($program, log_length, ptr::null_mut(), error_ptr);
-
-            let c_str = CStr::from_ptr(error_ptr);
-            log::error!("Program linking failed:\n{}", c_str.to_string_lossy());
-        } else {
-            log::debug!("Program linked successfully");
-        }
-    }};
-}
-#[macro_export]
 macro_rules! as_mut_expect {
     ($option:expr, $msg:expr) => {
         $option.as_mut().expect($msg)
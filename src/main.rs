@@ -1,174 +1,163 @@
-#![allow(non_snake_case, non_camel_case_types, static_mut_refs)]
+#![allow(non_snake_case, non_camel_case_types)]
 /*
     Please ignore the shitty code, im new to this :plead:
 */
 
+use asset_management::bounds::BoundingVolume;
 use asset_management::model::Model;
-use camera::{CameraConstructor, cpp_camera};
-use glfw::ffi::*;
-use log::{debug, error};
+use asset_management::picking;
+use camera::CameraPath;
+use clap::Parser;
+use cli::Cli;
+use engine::{Application, Engine, Event, FixedTimestep, HeadlessOptions};
+use log::{error, info};
 use nalgebra_glm::{self as glm};
-use shader::Shader;
-use shader::light::LightImpl;
-use std::ffi::{CStr, CString};
-use std::ptr::{self};
+use settings::{SceneSettings, Settings};
+use shader::light::{DirectionalLight, LightCollection, LightImpl, PointLight, SpotLight};
+use shader::{ShaderDefines, ShaderFeatures, ShaderVariants};
 use util::LinuxExitCode;
 mod asset_management;
 mod camera;
+mod cli;
+mod engine;
+mod framebuffer;
 mod gui;
+mod input;
 mod macros;
+mod settings;
 mod shader;
 mod texture;
 mod util;
+mod window;
+
+/// Point lights in the scene, passed to the shaders as `NR_POINT_LIGHTS`.
+const POINT_LIGHTS: usize = 4;
+
+struct Viewer {
+    scene: SceneSettings,
+    shader: Option<ShaderVariants>,
+    models: Vec<Model>,
+    /// Model framed by `frame_selected`, the whole scene when `None`.
+    selected: Option<usize>,
+    cameraSpotLight: Option<SpotLight>,
+    directionalLight: Option<DirectionalLight>,
+    pointLights: Option<LightCollection<PointLight, POINT_LIGHTS>>,
+}
 
-static mut SCREEN_WIDTH: i32 = 1920;
-static mut SCREEN_HEIGHT: i32 = 1080;
-
-static mut LASTFRAME: f64 = 0f64;
-static mut DELTATIME: f64 = 0f64;
-static mut FIRST_MOUSE: bool = true;
-static mut LAST_X: f32 = 0f32;
-static mut LAST_Y: f32 = 0f32;
-static mut CAMERA: cpp_camera = cpp_camera { camera: None };
-
-fn main() -> LinuxExitCode {
-    unsafe {
-        util::init_logging();
-        let ttf_arial = gui::TTF::new("fonts/Arial.ttf");
-        // return LinuxExitCode::OK;
-        if glfwInit() == 0 {
-            error!("GLFW failed to initialize!");
-            panic!("GLFW failed to initialize!");
+impl Viewer {
+    /// Selects the model under the cursor, or under the centre of the main view while the cursor
+    /// is captured.
+    fn pick(&mut self, engine: &Engine) {
+        let (window_width, window_height) = engine.window.size();
+        let (left, bottom, width, height) = engine.main_rect().pixels(window_width, window_height);
+        if width <= 0 || height <= 0 {
+            return;
         }
+        let captured =
+            unsafe { glfw::ffi::glfwGetInputMode(engine.window.handle(), glfw::ffi::CURSOR) }
+                == glfw::ffi::CURSOR_DISABLED;
+        let (x, y) = if captured {
+            (width as f64 / 2f64, height as f64 / 2f64)
+        } else {
+            // Relative to the main camera's view, whose rectangle starts bottom left.
+            let (x, y) = engine.window.cursor_position();
+            let top = window_height - bottom - height;
+            (x - left as f64, y - top as f64)
+        };
+        let ray = engine
+            .camera
+            .screen_ray(x as f32, y as f32, width as f32, height as f32);
+        let hit = picking::pick(&self.models, &ray);
+        match &hit {
+            Some(hit) => info!(
+                "Picked {} mesh {} triangle {} at {:.2} (barycentric {:.2}, {:.2}, {:.2})",
+                self.scene.models[hit.model].path,
+                hit.mesh,
+                hit.triangle,
+                hit.distance,
+                hit.barycentric.x,
+                hit.barycentric.y,
+                hit.barycentric.z
+            ),
+            None => info!("Picked nothing"),
+        }
+        self.selected = hit.map(|hit| hit.model);
+    }
 
-        let title = CString::new("My GLFW Window").unwrap();
-        glfwWindowHint(CONTEXT_VERSION_MAJOR as i32, 3);
-        glfwWindowHint(CONTEXT_VERSION_MINOR as i32, 3);
-        glfwWindowHint(OPENGL_PROFILE as i32, OPENGL_COMPAT_PROFILE as i32);
-        glfwWindowHint(OPENGL_FORWARD_COMPAT, TRUE);
-        glfwWindowHint(VISIBLE, FALSE);
-
-        let window: *mut GLFWwindow = glfwCreateWindow(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
-            title.as_ptr(),
-            ptr::null_mut(),
-            ptr::null_mut(),
-        );
-        if window.is_null() {
-            glfwTerminate();
-            error!("Failed to create GLFW window!");
-            return LinuxExitCode::ERR(1);
+    /// Sets the uniforms that don't change between frames, again after a shader reload.
+    fn set_static_uniforms(&self) {
+        let shaders = as_ref_except!(self.shader, "Viewer not initialized");
+        for (_, shader) in shaders.iter() {
+            shader.activate();
+            if let Some(pointLights) = &self.pointLights {
+                pointLights.set_uniform(shader, "pointLights");
+            }
+            if let Some(directionalLight) = &self.directionalLight {
+                directionalLight.set_uniform(shader, "dirLight");
+            }
         }
+    }
 
-        glfwMakeContextCurrent(window);
+    fn selection_bounds(&self) -> Option<BoundingVolume> {
+        let selected = match self.selected {
+            Some(i) => &self.models[i..=i],
+            None => &self.models[..],
+        };
+        selected
+            .iter()
+            .filter_map(Model::bounds)
+            .reduce(|a, b| a.union(&b))
+    }
+}
 
-        let mut _procname = CString::new("");
-        gl::load_with(|name| {
-            _procname = CString::new(name);
-            glfwGetProcAddress(_procname.as_mut().unwrap().as_ptr()) as *const _
-        });
-        debug!(
-            "gl_proc: {:#?}",
-            glfwGetProcAddress(_procname.as_mut().unwrap().as_ptr())
+impl Application for Viewer {
+    fn init(&mut self, engine: &mut Engine) {
+        let mut shaders = ShaderVariants::new(
+            &self.scene.shader.vertex,
+            &self.scene.shader.fragment,
+            &ShaderDefines::new()
+                .define("NR_POINT_LIGHTS", POINT_LIGHTS)
+                .define("MATERIAL_SHININESS", "32.0")
+                .define("height_scale", "0.1"),
+            ShaderFeatures::HEIGHT_MAPPING | ShaderFeatures::NORMAL_MAPPING,
         );
-        gl::Viewport(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT);
-        gl::Enable(gl::DEPTH_TEST);
-        gl::Enable(gl::CULL_FACE);
-
-        glfwSetFramebufferSizeCallback(window, Some(framebuffer_size_callback));
-        glfwSetCursorPosCallback(window, Some(mouse_callback));
-        glfwSetScrollCallback(window, Some(scroll_callback));
-        glfwSetInputMode(window, CURSOR, CURSOR_DISABLED);
-        CAMERA = cpp_camera::new(CameraConstructor(
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            Some(SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32),
-            None,
-            None,
-        ));
-
-        let version = CStr::from_ptr(gl::GetString(gl::VERSION) as *const _)
-            .to_str()
-            .expect("Unknown Version");
-        debug!("Using OpenGL version: {}", version);
-        let window_title = CString::new(format!("{:#?} - {}", title, version)).unwrap();
-        glfw::ffi::glfwSetWindowTitle(window, window_title.as_ptr());
-
-        let shader: Shader =
-            shader::ShaderConstructor("shaders/model_loading.vert", "shaders/model_loading.frag");
 
         let mut view = crate::util::glmaddon::mat4(1.032);
-        let projection = CAMERA.get_projection_matrix();
+        let projection = engine.camera.get_projection_matrix();
         let mut model = util::glmaddon::mat4(1.032);
 
         model = glm::rotate(&model, -0f32.to_radians(), &glm::vec3(1f32, 0.0, 0.0));
         view = glm::translate(&view, &glm::vec3(0f32, 0f32, -3f32));
 
-        let lightPositions = [
+        let lightPositions: [glm::Vec3; POINT_LIGHTS] = [
             glm::vec3(0.7f32, 0.2f32, 2.0f32),
             glm::vec3(2.3f32, -3.3f32, -4.0f32),
             glm::vec3(-4.0f32, 2.0f32, -12.0f32),
             glm::vec3(0.0f32, 0.0f32, -3.0f32),
         ];
 
-        let directionalLight = shader::light::DirectionalLight {
+        let directionalLight = DirectionalLight {
             direction: glm::vec3(-0.2f32, -1.0, -0.3),
             ambient: glm::vec3(0.05f32, 0.05, 0.05),
             diffuse: glm::vec3(0.4f32, 0.4, 0.4),
             specular: glm::vec3(0.5f32, 0.5, 0.5),
         };
 
-        let pointLightCollection = shader::light::LightCollection {
-            lights: [
-                shader::light::PointLight {
-                    position: lightPositions[0],
-                    ambient: glm::vec3(0.05f32, 0.05, 0.05),
-                    diffuse: glm::vec3(0.8f32, 0.8, 0.8),
-                    specular: glm::vec3(1f32, 1f32, 1f32),
-                    constant: 1f32,
-                    linear: 0.09f32,
-                    quadratic: 0.32f32,
-                },
-                shader::light::PointLight {
-                    position: lightPositions[1],
-                    ambient: glm::vec3(0.05f32, 0.05, 0.05),
-                    diffuse: glm::vec3(0.8f32, 0.8, 0.8),
-                    specular: glm::vec3(1f32, 1f32, 1f32),
-                    constant: 1f32,
-                    linear: 0.09f32,
-                    quadratic: 0.32f32,
-                },
-                shader::light::PointLight {
-                    position: lightPositions[2],
-                    ambient: glm::vec3(0.05f32, 0.05, 0.05),
-                    diffuse: glm::vec3(0.8f32, 0.8, 0.8),
-                    specular: glm::vec3(1f32, 1f32, 1f32),
-                    constant: 1f32,
-                    linear: 0.09f32,
-                    quadratic: 0.32f32,
-                },
-                shader::light::PointLight {
-                    position: lightPositions[3],
-                    ambient: glm::vec3(0.05f32, 0.05, 0.05),
-                    diffuse: glm::vec3(0.8f32, 0.8, 0.8),
-                    specular: glm::vec3(1f32, 1f32, 1f32),
-                    constant: 1f32,
-                    linear: 0.09f32,
-                    quadratic: 0.32f32,
-                },
-            ],
+        let pointLightCollection = LightCollection {
+            lights: lightPositions.map(|position| PointLight {
+                position,
+                ambient: glm::vec3(0.05f32, 0.05, 0.05),
+                diffuse: glm::vec3(0.8f32, 0.8, 0.8),
+                specular: glm::vec3(1f32, 1f32, 1f32),
+                constant: 1f32,
+                linear: 0.09f32,
+                quadratic: 0.32f32,
+            }),
         };
 
-        let mut cameraSpotLight: shader::light::SpotLight = shader::light::SpotLight {
-            position: CAMERA.get_position(),
-            direction: CAMERA.get_front(),
+        self.cameraSpotLight = Some(SpotLight {
+            position: engine.camera.get_position(),
+            direction: engine.camera.get_front(),
 
             ambient: glm::vec3(0.2f32, 0.2f32, 0.2f32),
             diffuse: glm::vec3(0.5f32, 0.5f32, 0.5f32),
@@ -180,180 +169,191 @@ fn main() -> LinuxExitCode {
             constant: 1f32,
             linear: 0.09f32,
             quadratic: 0.032f32,
-        };
-
-        shader.activate();
-        let backpack: Model = Model::new(
-            "models/backpack/backpack.obj",
-            None,
-            Some(glm::vec3(0.0, 0.0, 0.0)),
-            None,
-            Some(false),
-        );
-        let mut nanosuit: Model = Model::new(
-            "models/nanosuit/nanosuit.obj",
-            None,
-            Some(glm::vec3(0.0, 0.0, 10.0)),
-            Some((90f32.to_radians(), glm::vec3(0.0, 1.0, 0.0))),
-            Some(false),
-        );
-
-        shader.setMat4("view", view, gl::FALSE);
-        shader.setMat4("projection", projection, gl::FALSE);
-        shader.setMat4("model", model, gl::FALSE);
-
-        pointLightCollection.set_uniform(&shader, "pointLights");
-        directionalLight.set_uniform(&shader, "dirLight");
-
-        glfwShowWindow(window);
-        while glfwWindowShouldClose(window) == 0 {
-            UPDATE_DELTATIME();
-            process_input(window);
-
-            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-
-            nanosuit.rotation.0 += DELTATIME as f32;
-
-            cameraSpotLight.position = CAMERA.get_position();
-            cameraSpotLight.direction = CAMERA.get_front();
+        });
 
+        self.models = self
+            .scene
+            .models
+            .iter()
+            .map(|m| {
+                Model::new(
+                    &m.path,
+                    None,
+                    Some(glm::make_vec3(&m.position)),
+                    m.rotation
+                        .map(|[angle, x, y, z]| (angle.to_radians(), glm::vec3(x, y, z))),
+                    Some(m.height_mapping),
+                )
+            })
+            .collect();
+
+        // Compile every variant the models need up front so each gets its uniforms.
+        for features in self.models.iter().flat_map(Model::features) {
+            shaders.prepare(features);
+        }
+        for (_, shader) in shaders.iter() {
             shader.activate();
-
-            cameraSpotLight.set_uniform(&shader, "spotLight");
-            shader.setVec3("viewPos", CAMERA.get_position());
-            shader.setMat4("view", CAMERA.get_view_matrix(), gl::FALSE);
-            shader.setMat4("projection", CAMERA.get_projection_matrix(), gl::FALSE);
-
-            backpack.draw(&shader);
-            nanosuit.draw(&shader);
-
-            glfwSwapBuffers(window);
-            glfwPollEvents();
+            unsafe {
+                shader.setMat4("view", view, gl::FALSE);
+                shader.setMat4("projection", projection, gl::FALSE);
+                shader.setMat4("model", model, gl::FALSE);
+            }
         }
 
-        glfwDestroyWindow(window);
-        glfwTerminate();
+        self.shader = Some(shaders);
+        self.directionalLight = Some(directionalLight);
+        self.pointLights = Some(pointLightCollection);
+        self.set_static_uniforms();
     }
-    LinuxExitCode::OK
-}
 
-extern "C" fn framebuffer_size_callback(_window: *mut GLFWwindow, width: i32, height: i32) {
-    unsafe {
-        SCREEN_WIDTH = width;
-        SCREEN_HEIGHT = height;
-        gl::Viewport(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT);
-        CAMERA.set_aspect_ratio(SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32);
-    }
-}
-extern "C" fn process_input(window: *mut GLFWwindow) {
-    unsafe {
-        if glfwGetKey(window, KEY_ESCAPE) == PRESS {
-            glfwSetWindowShouldClose(window, TRUE)
+    fn on_event(&mut self, engine: &mut Engine, event: &Event) {
+        if let Event::MouseButton {
+            button: glfw::ffi::MOUSE_BUTTON_LEFT,
+            pressed: true,
+            ..
+        } = event
+        {
+            self.pick(engine);
         }
+    }
 
-        if glfwGetKey(window, KEY_1) == PRESS {
-            glfwSetInputMode(window, CURSOR, CURSOR_NORMAL);
-            FIRST_MOUSE = true;
+    fn update(&mut self, engine: &mut Engine, dt: f64) {
+        for (model, settings) in self.models.iter_mut().zip(&self.scene.models) {
+            model.store_previous();
+            model.rotation.0 += settings.spin * dt as f32;
         }
-        if glfwGetKey(window, KEY_2) == PRESS {
-            glfwSetInputMode(window, CURSOR, CURSOR_DISABLED);
-        }
-        if glfwGetKey(window, KEY_W) == PRESS {
-            CAMERA.process_keyboard(camera::CameraMovement::FORWARD, DELTATIME);
+        if engine.input.pressed("frame_selected")
+            && let Some(bounds) = self.selection_bounds()
+        {
+            engine.camera.frame_bounds(bounds.min, bounds.max);
         }
-        if glfwGetKey(window, KEY_S) == PRESS {
-            CAMERA.process_keyboard(camera::CameraMovement::BACKWARD, DELTATIME);
+    }
+
+    fn render(&mut self, engine: &mut Engine, alpha: f32) {
+        if let Some(watcher) = &mut engine.shader_watcher
+            && let Some(shaders) = &mut self.shader
+        {
+            let mut reloaded = false;
+            for (_, shader) in shaders.iter_mut() {
+                reloaded |= watcher.reload(shader);
+            }
+            if reloaded {
+                self.set_static_uniforms();
+            }
         }
-        if glfwGetKey(window, KEY_A) == PRESS {
-            CAMERA.process_keyboard(camera::CameraMovement::LEFT, DELTATIME);
+        // Models can need new variants after loading or toggling height mapping; compile them
+        // before the per-frame uniforms are set.
+        let shaders = as_mut_expect!(self.shader, "Viewer not initialized");
+        let mut compiled = false;
+        for features in self.models.iter().flat_map(Model::features) {
+            compiled |= shaders.prepare(features);
         }
-        if glfwGetKey(window, KEY_D) == PRESS {
-            CAMERA.process_keyboard(camera::CameraMovement::RIGHT, DELTATIME);
+        if compiled {
+            self.set_static_uniforms();
         }
+        let shaders = as_ref_except!(self.shader, "Viewer not initialized");
+        let cameraSpotLight = as_mut_expect!(self.cameraSpotLight, "Viewer not initialized");
+        let camera = engine.render_camera();
+        unsafe {
+            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
-        let mut mousepos = (0.0f64, 0.0f64);
-        glfwGetCursorPos(window, &mut mousepos.0, &mut mousepos.1);
+            cameraSpotLight.position = camera.get_position();
+            cameraSpotLight.direction = camera.get_front();
 
-        if glfwGetKey(window, KEY_DOWN) == PRESS
-            || glfwGetKey(window, KEY_LEFT) == PRESS
-            || glfwGetKey(window, KEY_RIGHT) == PRESS
-            || glfwGetKey(window, KEY_UP) == PRESS
-        {
-            let xpos = mousepos.0 as f32;
-            let ypos = mousepos.1 as f32;
+            for (_, shader) in shaders.iter() {
+                shader.activate();
 
-            if FIRST_MOUSE {
-                LAST_X = xpos;
-                LAST_Y = ypos;
-                FIRST_MOUSE = false;
+                cameraSpotLight.set_uniform(shader, "spotLight");
+                shader.setVec3("viewPos", camera.get_position());
+                shader.setMat4("view", camera.get_view_matrix(), gl::FALSE);
+                shader.setMat4("projection", camera.get_projection_matrix(), gl::FALSE);
             }
+        }
 
-            let xoffset = xpos - LAST_X;
-            let yoffset = LAST_Y - ypos;
-
-            let sensitivity = CAMERA.get_sensitivity() * 90f32;
-
-            let incrX = sensitivity * {
-                if glfwGetKey(window, KEY_LEFT) == PRESS {
-                    1f32
-                } else if glfwGetKey(window, KEY_RIGHT) == PRESS {
-                    -1f32
-                } else {
-                    0f32
-                }
-            };
-
-            let incrY = sensitivity * {
-                if glfwGetKey(window, KEY_UP) == PRESS {
-                    1f32
-                } else if glfwGetKey(window, KEY_DOWN) == PRESS {
-                    -1f32
-                } else {
-                    0f32
-                }
-            };
-
-            LAST_X = xpos + incrX;
-            LAST_Y = ypos + incrY;
-
-            CAMERA.process_mouse(xoffset, yoffset);
+        let frustum = camera.get_frustum();
+        for model in &self.models {
+            model.draw(shaders, alpha, Some(&frustum));
         }
     }
 }
 
-extern "C" fn mouse_callback(_window: *mut GLFWwindow, xposIn: f64, yposIn: f64) {
-    if unsafe { glfwGetInputMode(_window, CURSOR) } == CURSOR_DISABLED {
-        let xpos = xposIn as f32;
-        let ypos = yposIn as f32;
-        unsafe {
-            if FIRST_MOUSE {
-                LAST_X = xpos;
-                LAST_Y = ypos;
-                FIRST_MOUSE = false;
+fn main() -> LinuxExitCode {
+    let cli = Cli::parse();
+    let settings = Settings::load(&cli);
+    util::init_logging(settings.as_ref().ok().and_then(|s| s.log_level));
+    let settings = match settings {
+        Ok(settings) => settings,
+        Err(e) => {
+            error!("{}", e);
+            return LinuxExitCode::ERR(1);
+        }
+    };
+    let _ttf_arial = gui::TTF::new("fonts/Arial.ttf");
+
+    let mut engine = match Engine::new(&settings.window) {
+        Ok(engine) => engine,
+        Err(_) => return LinuxExitCode::ERR(1),
+    };
+    engine.camera.motion = settings.camera;
+    engine.timestep =
+        FixedTimestep::new(settings.simulation.tick_rate, settings.simulation.max_steps);
+    shader::set_strict_uniforms(settings.shaders.strict_uniforms);
+    if settings.shaders.hot_reload {
+        let root = settings
+            .shaders
+            .watch_dir
+            .clone()
+            .unwrap_or_else(asset_management::get_assets_dir);
+        engine.watch_shaders(&root);
+    }
+    if let Some(path) = &cli.camera_path {
+        engine.set_camera_path_file(path);
+        if path.exists() {
+            match CameraPath::load(path) {
+                Ok(camera_path) => engine.play_camera_path(camera_path, cli.loop_path),
+                Err(e) => {
+                    error!("{}", e);
+                    return LinuxExitCode::ERR(1);
+                }
             }
-
-            let xoffset = xpos - LAST_X;
-            let yoffset = LAST_Y - ypos;
-
-            LAST_X = xpos;
-            LAST_Y = ypos;
-
-            CAMERA.process_mouse(xoffset, yoffset);
         }
     }
-}
-
-extern "C" fn scroll_callback(_window: *mut GLFWwindow, _: f64, yoffset: f64) {
-    unsafe {
-        CAMERA.process_scroll(yoffset as f32);
+    let viewer = Box::new(Viewer {
+        scene: settings.scene,
+        shader: None,
+        models: Vec::new(),
+        selected: None,
+        cameraSpotLight: None,
+        directionalLight: None,
+        pointLights: None,
+    });
+    if let Some(frames) = cli.headless {
+        let mut options = HeadlessOptions::new(frames, cli.output);
+        options.reference = cli.reference;
+        options.tolerance = cli.tolerance;
+        return match engine.run_headless(viewer, &options) {
+            Ok(0) => LinuxExitCode::OK,
+            Ok(failures) => {
+                error!("{} frame(s) differ from the reference images", failures);
+                LinuxExitCode::ERR(2)
+            }
+            Err(e) => {
+                error!("Headless run failed: {}", e);
+                LinuxExitCode::ERR(1)
+            }
+        };
     }
-}
 
-fn UPDATE_DELTATIME() {
-    unsafe {
-        let currentframe = glfwGetTime();
-        DELTATIME = currentframe - LASTFRAME;
-        LASTFRAME = currentframe;
+    let recording = match (&cli.record, &cli.replay) {
+        (Some(path), _) => engine.record_input(path),
+        (_, Some(path)) => engine.replay_input(path),
+        _ => Ok(()),
+    };
+    if let Err(e) = recording {
+        error!("{}", e);
+        return LinuxExitCode::ERR(1);
     }
+    engine.run(viewer);
+    LinuxExitCode::OK
 }
@@ -0,0 +1,335 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::LevelFilter;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::asset_management::HOME;
+use crate::camera::CameraMotion;
+use crate::cli::Cli;
+use crate::window::FullscreenMode;
+
+const DEFAULT_SETTINGS: &str = include_str!("assets/config/settings.yml");
+
+/// Startup settings for the viewer, layered from lowest to highest priority: the built-in
+/// `config/settings.yml`, the user settings file, `--config`, `--scene` and the command line.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawSettings")]
+pub struct Settings {
+    /// Overrides the root level from `log4rs.yml`.
+    pub log_level: Option<LevelFilter>,
+    pub window: WindowSettings,
+    pub camera: CameraMotion,
+    pub simulation: SimulationSettings,
+    pub shaders: ShaderDebugSettings,
+    pub scene: SceneSettings,
+}
+
+/// [`Settings`] as written in the files, before the log level is parsed.
+#[derive(Deserialize)]
+struct RawSettings {
+    log_level: Option<String>,
+    window: WindowSettings,
+    #[serde(default)]
+    camera: CameraMotion,
+    #[serde(default)]
+    simulation: SimulationSettings,
+    #[serde(default)]
+    shaders: ShaderDebugSettings,
+    scene: SceneSettings,
+}
+
+impl TryFrom<RawSettings> for Settings {
+    type Error = String;
+
+    fn try_from(raw: RawSettings) -> Result<Self, Self::Error> {
+        let log_level = match raw.log_level {
+            Some(level) => Some(
+                level
+                    .parse()
+                    .map_err(|_| format!("Invalid log level '{}'", level))?,
+            ),
+            None => None,
+        };
+        let simulation = raw.simulation;
+        if !(simulation.tick_rate.is_finite() && simulation.tick_rate > 0f64) {
+            return Err(format!(
+                "simulation.tick_rate must be positive, got {}",
+                simulation.tick_rate
+            ));
+        }
+        if simulation.max_steps == 0 {
+            return Err("simulation.max_steps must be at least 1".to_string());
+        }
+        Ok(Settings {
+            log_level,
+            window: raw.window,
+            camera: raw.camera,
+            simulation,
+            shaders: raw.shaders,
+            scene: raw.scene,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WindowSettings {
+    pub width: i32,
+    pub height: i32,
+    pub title: String,
+    pub fullscreen: FullscreenMode,
+    /// Index into [`crate::window::Monitor::all`]; the primary monitor if unset.
+    pub monitor: Option<usize>,
+    /// Preferred refresh rate for exclusive fullscreen.
+    pub refresh_rate: Option<i32>,
+    pub vsync: bool,
+    /// Samples per pixel, 0 to disable multisampling.
+    pub msaa: u32,
+    pub gl_version: GlVersion,
+    /// Reverse-Z float depth buffer and no far plane; needs GL 4.5 or `GL_ARB_clip_control` for
+    /// the reversed range, and keeps only the infinite far plane without it.
+    #[serde(default)]
+    pub reverse_z: bool,
+    /// Image asset used as the window icon.
+    pub icon: Option<String>,
+}
+
+/// The fixed simulation clock, see [`crate::engine::FixedTimestep`].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct SimulationSettings {
+    /// Ticks per second.
+    pub tick_rate: f64,
+    /// Most ticks run in one frame; time beyond that is dropped after a stall.
+    pub max_steps: u32,
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        Self {
+            tick_rate: 60f64,
+            max_steps: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShaderDebugSettings {
+    /// Recompile shaders when their files change.
+    #[serde(default)]
+    pub hot_reload: bool,
+    /// Directory watched instead of the installed assets, e.g. `src/assets` while developing.
+    pub watch_dir: Option<PathBuf>,
+    /// Log uniforms that are set but missing or of another type as errors, not warnings.
+    #[serde(default)]
+    pub strict_uniforms: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SceneSettings {
+    pub shader: ShaderSettings,
+    pub models: Vec<ModelSettings>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShaderSettings {
+    pub vertex: String,
+    pub fragment: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelSettings {
+    pub path: String,
+    #[serde(default)]
+    pub position: [f32; 3],
+    /// Angle in degrees followed by the axis.
+    pub rotation: Option<[f32; 4]>,
+    /// Radians per second around the rotation axis.
+    #[serde(default)]
+    pub spin: f32,
+    #[serde(default)]
+    pub height_mapping: bool,
+}
+
+impl ModelSettings {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            position: [0f32; 3],
+            rotation: None,
+            spin: 0f32,
+            height_mapping: false,
+        }
+    }
+}
+
+/// An OpenGL context version written as `MAJOR.MINOR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct GlVersion {
+    pub major: i32,
+    pub minor: i32,
+}
+
+impl FromStr for GlVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid OpenGL version '{}', expected MAJOR.MINOR", s);
+        let (major, minor) = s.trim().split_once('.').ok_or_else(invalid)?;
+        Ok(GlVersion {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl TryFrom<String> for GlVersion {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for GlVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl Settings {
+    /// `$XDG_CONFIG_HOME/windowproject/settings.yml`, or `~/.config/windowproject/settings.yml`.
+    pub fn user_path() -> PathBuf {
+        let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => Path::new(&HOME.as_os_str()).join(".config"),
+        };
+        config_dir.join(env!("CARGO_PKG_NAME")).join("settings.yml")
+    }
+
+    /// Builds the settings for this run. A missing user settings file is fine; one that
+    /// exists but can't be read or parsed is an error, as is a missing `--config` or `--scene`.
+    pub fn load(cli: &Cli) -> Result<Self, String> {
+        let mut value: Value = serde_yaml::from_str(DEFAULT_SETTINGS)
+            .map_err(|e| format!("Built-in settings are invalid: {}", e))?;
+
+        let user_path = Self::user_path();
+        if user_path.is_file() {
+            merge(&mut value, read_yaml(&user_path)?);
+        }
+        if let Some(path) = &cli.config {
+            merge(&mut value, read_yaml(path)?);
+        }
+        if let Some(path) = &cli.scene {
+            let mut scene = Mapping::new();
+            scene.insert("scene".into(), read_yaml(path)?);
+            merge(&mut value, Value::Mapping(scene));
+        }
+
+        let mut settings: Settings =
+            serde_yaml::from_value(value).map_err(|e| format!("Invalid settings: {}", e))?;
+        settings.apply_cli(cli);
+        Ok(settings)
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        let window = &mut self.window;
+        if let Some(width) = cli.width {
+            window.width = width;
+        }
+        if let Some(height) = cli.height {
+            window.height = height;
+        }
+        if let Some(title) = &cli.title {
+            window.title = title.clone();
+        }
+        if cli.windowed {
+            window.fullscreen = FullscreenMode::Windowed;
+        } else if let Some(mode) = cli.fullscreen {
+            window.fullscreen = mode;
+        }
+        if let Some(monitor) = cli.monitor {
+            window.monitor = Some(monitor);
+        }
+        if cli.vsync || cli.no_vsync {
+            window.vsync = cli.vsync;
+        }
+        if let Some(msaa) = cli.msaa {
+            window.msaa = msaa;
+        }
+        if let Some(gl_version) = cli.gl_version {
+            window.gl_version = gl_version;
+        }
+        if cli.reverse_z {
+            window.reverse_z = true;
+        }
+        if let Some(watch_dir) = &cli.hot_reload {
+            self.shaders.hot_reload = true;
+            if let Some(dir) = watch_dir {
+                self.shaders.watch_dir = Some(dir.clone());
+            }
+        }
+        if let Some(level) = cli.log_level {
+            self.log_level = Some(level);
+        }
+        if !cli.models.is_empty() {
+            self.scene.models = cli.models.iter().map(|p| ModelSettings::new(p)).collect();
+        }
+    }
+}
+
+fn read_yaml(path: &Path) -> Result<Value, String> {
+    let yaml = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_yaml::from_str(&yaml)
+        .map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))
+}
+
+/// Recursively overlays `overlay` onto `base`; mappings merge key by key, anything else
+/// (including lists) is replaced.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (_, Value::Null) => {}
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The built-in settings with `overlay` merged on top.
+    fn parse(overlay: &str) -> Result<Settings, serde_yaml::Error> {
+        let mut value: Value = serde_yaml::from_str(DEFAULT_SETTINGS).unwrap();
+        merge(&mut value, serde_yaml::from_str(overlay).unwrap());
+        serde_yaml::from_value(value)
+    }
+
+    #[test]
+    fn simulation_defaults_load() {
+        let settings = parse("{}").unwrap();
+        assert_eq!(settings.simulation.tick_rate, 60f64);
+        assert_eq!(settings.simulation.max_steps, 5);
+    }
+
+    #[test]
+    fn rejects_a_stopped_simulation() {
+        assert!(parse("simulation: { tick_rate: 0.0 }").is_err());
+        assert!(parse("simulation: { tick_rate: -30.0 }").is_err());
+        assert!(parse("simulation: { max_steps: 0 }").is_err());
+        assert!(parse("simulation: { tick_rate: 120.0, max_steps: 1 }").is_ok());
+    }
+}
@@ -1,62 +1,386 @@
 #![allow(non_snake_case, non_camel_case_types, dead_code)]
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{ffi::CString, io::Read};
 
-use gl::types::{self, GLboolean, GLuint};
+use gl::types::{self, GLboolean, GLenum, GLint, GLuint};
 use nalgebra_glm as glm;
 
 use crate::asset_management::get_asset;
 mod buffer;
+pub mod builder;
+pub mod compute;
+pub mod error;
+pub mod hot_reload;
 pub mod light;
 pub mod material;
+pub mod preprocessor;
+pub mod reflection;
+pub mod variants;
 pub mod vertexattrib;
+pub use builder::ShaderBuilder;
+pub use error::ShaderError;
+pub use hot_reload::ShaderWatcher;
+pub use preprocessor::{PreprocessedSource, ShaderDefines};
+pub use reflection::ShaderReflection;
+pub use variants::{ShaderFeatures, ShaderVariants};
+
+static STRICT_UNIFORMS: AtomicBool = AtomicBool::new(false);
+
+/// Report uniforms that are set but missing or of another type as errors instead of warnings.
+pub fn set_strict_uniforms(strict: bool) {
+    STRICT_UNIFORMS.store(strict, Ordering::Relaxed);
+}
+
+/// A programmable pipeline stage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    TessControl,
+    TessEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+    fn gl_kind(self) -> GLenum {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::TessControl => gl::TESS_CONTROL_SHADER,
+            ShaderStage::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            ShaderStage::Geometry => gl::GEOMETRY_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+            ShaderStage::Compute => gl::COMPUTE_SHADER,
+        }
+    }
+
+    /// First core GL version with the stage.
+    pub fn min_version(self) -> (i32, i32) {
+        match self {
+            ShaderStage::Vertex | ShaderStage::Fragment => (2, 0),
+            ShaderStage::Geometry => (3, 2),
+            ShaderStage::TessControl | ShaderStage::TessEvaluation => (4, 0),
+            ShaderStage::Compute => (4, 3),
+        }
+    }
+
+    /// Name reported for code given to [`ShaderBuilder::source`].
+    fn source_name(self) -> &'static str {
+        match self {
+            ShaderStage::Vertex => "<vertex>",
+            ShaderStage::TessControl => "<tess_control>",
+            ShaderStage::TessEvaluation => "<tess_evaluation>",
+            ShaderStage::Geometry => "<geometry>",
+            ShaderStage::Fragment => "<fragment>",
+            ShaderStage::Compute => "<compute>",
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "Vertex"),
+            ShaderStage::TessControl => write!(f, "Tessellation control"),
+            ShaderStage::TessEvaluation => write!(f, "Tessellation evaluation"),
+            ShaderStage::Geometry => write!(f, "Geometry"),
+            ShaderStage::Fragment => write!(f, "Fragment"),
+            ShaderStage::Compute => write!(f, "Compute"),
+        }
+    }
+}
+
+/// Version of the current context.
+pub fn gl_version() -> (i32, i32) {
+    let (mut major, mut minor) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+    (major, minor)
+}
+
+const ERROR_VERTEX: &str = "#version 330 core
+layout(location = 0) in vec3 aPos;
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+void main()
+{
+    gl_Position = projection * view * model * vec4(aPos, 1.0);
+}
+";
+const ERROR_FRAGMENT: &str = "#version 330 core
+out vec4 FragColor;
+void main()
+{
+    FragColor = vec4(1.0, 0.0, 1.0, 1.0);
+}
+";
+
 pub struct Shader {
     ID: GLuint,
+    /// Path of each stage's root file, in pipeline order.
+    stages: Vec<(ShaderStage, String)>,
+    defines: ShaderDefines,
+    /// Code of stages given with [`ShaderBuilder::source`], by the name used as their path.
+    inline: Vec<(String, String)>,
+    /// Every file the program was built from, includes too.
+    sources: Vec<String>,
+    /// [`ShaderWatcher`] generation the program was last checked against.
+    generation: u64,
+    reflection: ShaderReflection,
+    /// Uniform names already reported as missing or mistyped.
+    reported: RefCell<HashSet<String>>,
+    /// Whether `ID` is [`Shader::error_shader`] standing in for `stages`, which failed to build.
+    fallback: bool,
 }
 
+/// Loads a shader from the assets, or logs why it could not be built and returns
+/// [`Shader::error_shader`] in its place.
 #[inline(always)]
 pub fn ShaderConstructor(vertexPath: &str, fragmentPath: &str) -> Shader {
+    ShaderConstructorDefines(vertexPath, fragmentPath, &ShaderDefines::new())
+}
+
+/// Like [`ShaderConstructor`], with `defines` added to both stages.
+pub fn ShaderConstructorDefines(
+    vertexPath: &str,
+    fragmentPath: &str,
+    defines: &ShaderDefines,
+) -> Shader {
+    ShaderBuilder::new()
+        .vertex(vertexPath)
+        .fragment(fragmentPath)
+        .defines(defines)
+        .build_or_error_shader()
+}
+
+impl Shader {
+    /// Builds a program from the asset files, expanding includes and adding `defines`.
+    pub fn from_files(
+        vertexPath: &str,
+        fragmentPath: &str,
+        defines: &ShaderDefines,
+    ) -> Result<Shader, ShaderError> {
+        ShaderBuilder::new()
+            .vertex(vertexPath)
+            .fragment(fragmentPath)
+            .defines(defines)
+            .build()
+    }
+
+    /// Builds a program from code in memory; includes are still read from the assets.
+    pub fn from_sources(
+        vertexCode: &str,
+        fragmentCode: &str,
+        defines: &ShaderDefines,
+    ) -> Result<Shader, ShaderError> {
+        ShaderBuilder::new()
+            .source(ShaderStage::Vertex, vertexCode)
+            .source(ShaderStage::Fragment, fragmentCode)
+            .defines(defines)
+            .build()
+    }
+
+    /// A flat magenta program that draws anything with `aPos`, `model`, `view` and `projection`,
+    /// used in place of shaders that failed to build. If even that fails the program is 0, which
+    /// draws nothing.
+    pub fn error_shader() -> Shader {
+        Self::from_sources(ERROR_VERTEX, ERROR_FRAGMENT, &ShaderDefines::new()).unwrap_or_else(
+            |e| {
+                log::error!("Built-in error shader failed to build: {}", e);
+                Shader {
+                    ID: 0,
+                    stages: Vec::new(),
+                    defines: ShaderDefines::new(),
+                    inline: Vec::new(),
+                    sources: Vec::new(),
+                    generation: 0,
+                    reflection: ShaderReflection::default(),
+                    reported: RefCell::new(HashSet::new()),
+                    fallback: false,
+                }
+            },
+        )
+    }
+
+    /// [`Shader::error_shader`] standing in for the program `stages` would build. It keeps their
+    /// paths, defines and files, so [`ShaderWatcher::reload`] retries them once they change.
+    fn error_fallback(
+        stages: Vec<(ShaderStage, String)>,
+        defines: &ShaderDefines,
+        inline: Vec<(String, String)>,
+    ) -> Shader {
+        let read = |path: &str| match inline.iter().find(|(name, _)| name == path) {
+            Some((_, code)) => Ok(code.clone()),
+            None => read_source(path, None),
+        };
+        // Includes are only known for stages that still preprocess.
+        let mut files = Vec::new();
+        for (_, path) in &stages {
+            match preprocessor::preprocess(path, defines, &read) {
+                Ok(source) => files.extend(source.files),
+                Err(_) => files.push(path.clone()),
+            }
+        }
+
+        let mut shader = Shader::error_shader();
+        shader.sources = watched_files(files, &inline);
+        shader.stages = stages;
+        shader.defines = defines.clone();
+        shader.inline = inline;
+        shader.fallback = true;
+        shader
+    }
+
+    /// Reads the sources from `root` instead of the installed assets if given.
+    fn build(
+        stages: Vec<(ShaderStage, String)>,
+        defines: &ShaderDefines,
+        inline: Vec<(String, String)>,
+        root: Option<&Path>,
+    ) -> Result<Shader, ShaderError> {
+        let read = |path: &str| match inline.iter().find(|(name, _)| name == path) {
+            Some((_, code)) => Ok(code.clone()),
+            None => read_source(path, root),
+        };
+        let mut preprocessed = Vec::with_capacity(stages.len());
+        for (stage, path) in &stages {
+            preprocessed.push((*stage, preprocessor::preprocess(path, defines, &read)?));
+        }
+
+        let ID = compile_program(&preprocessed)?;
+        let files = preprocessed
+            .iter()
+            .flat_map(|(_, source)| source.files.iter().cloned());
+        let sources = watched_files(files, &inline);
+        Ok(Shader {
+            ID,
+            stages,
+            defines: defines.clone(),
+            inline,
+            sources,
+            generation: 0,
+            reflection: ShaderReflection::query(ID),
+            reported: RefCell::new(HashSet::new()),
+            fallback: false,
+        })
+    }
+
+    /// The stage paths joined with `+`, for messages.
+    pub fn name(&self) -> String {
+        let paths: Vec<_> = self.stages.iter().map(|(_, path)| path.as_str()).collect();
+        paths.join(" + ")
+    }
+
+    pub fn stages(&self) -> impl Iterator<Item = ShaderStage> + '_ {
+        self.stages.iter().map(|(stage, _)| *stage)
+    }
+
+    /// Whether the requested stages failed to build and the error shader is drawing instead.
+    pub fn is_fallback(&self) -> bool {
+        self.fallback
+    }
+}
+
+/// `files` without duplicates and without the names of code given in memory.
+fn watched_files(
+    files: impl IntoIterator<Item = String>,
+    inline: &[(String, String)],
+) -> Vec<String> {
+    let mut watched = Vec::new();
+    for file in files {
+        let is_inline = inline.iter().any(|(name, _)| *name == file);
+        if !is_inline && !watched.contains(&file) {
+            watched.push(file);
+        }
+    }
+    watched
+}
+
+/// Reads a shader asset as UTF-8, from `root` if given.
+fn read_source(path: &str, root: Option<&Path>) -> Result<String, ShaderError> {
+    let missing = || ShaderError::MissingAsset {
+        path: path.to_string(),
+    };
+    let invalid = |reason: String| ShaderError::InvalidSource {
+        path: path.to_string(),
+        reason,
+    };
+    let mut file = match root {
+        Some(root) => File::open(root.join(path)).map_err(|_| missing())?,
+        None => get_asset(path).map_err(|_| missing())?,
+    };
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .map_err(|e| invalid(e.to_string()))?;
+    let code = String::from_utf8(bytes).map_err(|e| invalid(format!("not UTF-8: {}", e)))?;
+    if code.contains('\0') {
+        return Err(invalid("contains a NUL byte".to_string()));
+    }
+    Ok(code)
+}
+
+/// Compiles and links a program, with compile errors located in the original files.
+fn compile_program(stages: &[(ShaderStage, PreprocessedSource)]) -> Result<GLuint, ShaderError> {
     unsafe {
-        // Read shader files
-        let mut vertexCode = String::new();
-        get_asset(vertexPath)
-            .expect("Failed to read vertex shader file")
-            .read_to_string(&mut vertexCode)
-            .unwrap();
-        let mut fragmentCode = String::new();
-        get_asset(fragmentPath)
-            .expect("Failed to read vertex shader file")
-            .read_to_string(&mut fragmentCode)
-            .unwrap();
-
-        // Convert to CString
-        let vertexSource =
-            CString::new(vertexCode).expect("Could not convert vertex shader to CString");
-        let fragmentSource =
-            CString::new(fragmentCode).expect("Could not convert fragment shader to CString");
-
-        let vertexShader: u32 = gl::CreateShader(gl::VERTEX_SHADER);
-        gl::ShaderSource(vertexShader, 1, &vertexSource.as_ptr(), std::ptr::null());
-        gl::CompileShader(vertexShader);
-        check_shader_compile(vertexShader);
-        let fragmentShader: u32 = gl::CreateShader(gl::FRAGMENT_SHADER);
-        gl::ShaderSource(
-            fragmentShader,
-            1,
-            &fragmentSource.as_ptr(),
-            std::ptr::null(),
-        );
-        gl::CompileShader(fragmentShader);
-        check_shader_compile(fragmentShader);
+        let mut shaders = Vec::with_capacity(stages.len());
+        for (stage, source) in stages {
+            match compile_stage(*stage, source) {
+                Ok(shader) => shaders.push(shader),
+                Err(e) => {
+                    for shader in shaders {
+                        gl::DeleteShader(shader);
+                    }
+                    return Err(e);
+                }
+            }
+        }
 
         let ID = gl::CreateProgram();
-        gl::AttachShader(ID, vertexShader);
-        gl::AttachShader(ID, fragmentShader);
+        for &shader in &shaders {
+            gl::AttachShader(ID, shader);
+        }
         gl::LinkProgram(ID);
-        check_program_link(ID);
-        gl::DeleteShader(vertexShader);
-        gl::DeleteShader(fragmentShader);
+        for shader in shaders {
+            gl::DeleteShader(shader);
+        }
+        if let Err(log) = check_program_link(ID) {
+            gl::DeleteProgram(ID);
+            return Err(ShaderError::Link { log });
+        }
+        Ok(ID)
+    }
+}
 
-        Shader { ID }
+unsafe fn compile_stage(
+    stage: ShaderStage,
+    source: &PreprocessedSource,
+) -> Result<GLuint, ShaderError> {
+    let path = source.files.first().cloned().unwrap_or_default();
+    let code = CString::new(source.code.as_str()).map_err(|_| ShaderError::InvalidSource {
+        path: path.clone(),
+        reason: "contains a NUL byte".to_string(),
+    })?;
+    unsafe {
+        let shader = gl::CreateShader(stage.gl_kind());
+        gl::ShaderSource(shader, 1, &code.as_ptr(), std::ptr::null());
+        gl::CompileShader(shader);
+        if let Err(log) = check_shader_compile(shader) {
+            gl::DeleteShader(shader);
+            return Err(ShaderError::Compile {
+                stage,
+                path,
+                entries: source.parse_log(&log),
+            });
+        }
+        Ok(shader)
     }
 }
 
@@ -69,33 +393,74 @@ impl Shader {
             gl::UseProgram(self.ID);
         }
     }
+    /// Active uniforms and attributes of the current program.
+    pub fn reflection(&self) -> &ShaderReflection {
+        &self.reflection
+    }
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.reflection.uniform(name).is_some()
+    }
+    /// Location of the uniform `name` if it exists and `accepts` its type, otherwise -1, which GL
+    /// ignores. Each bad name is reported once per program.
+    fn uniform_location(&self, name: &str, accepts: fn(GLenum) -> bool, expected: &str) -> GLint {
+        match self.reflection.uniform(name) {
+            Some(uniform) if accepts(uniform.kind) => uniform.location,
+            Some(uniform) => {
+                self.report_uniform(
+                    name,
+                    &format!(
+                        "is a {} but was set as {}",
+                        reflection::type_name(uniform.kind),
+                        expected
+                    ),
+                );
+                -1
+            }
+            None => {
+                self.report_uniform(name, "does not exist or was optimized out");
+                -1
+            }
+        }
+    }
+    fn report_uniform(&self, name: &str, problem: &str) {
+        if !self.reported.borrow_mut().insert(name.to_string()) {
+            return;
+        }
+        let message = format!("Uniform '{}' in {} {}", name, self.name(), problem);
+        if STRICT_UNIFORMS.load(Ordering::Relaxed) {
+            log::error!("{}", message);
+        } else {
+            log::warn!("{}", message);
+        }
+    }
     pub unsafe fn setBool(&self, name: &str, value: bool) {
         unsafe {
             gl::Uniform1i(
-                gl::GetUniformLocation(
-                    self.ID,
-                    CString::new(name)
-                        .expect(format!("Could not convert {} to CString", name).as_str())
-                        .as_ptr(),
-                ),
+                self.uniform_location(name, reflection::is_int_like, "bool"),
                 value as i32,
             );
         }
     }
     pub unsafe fn setInt(&self, name: &str, value: i32) {
         unsafe {
-            gl::Uniform1i(gl::GetUniformLocation(self.ID, name_to_ptr(name).1), value);
+            gl::Uniform1i(
+                self.uniform_location(name, reflection::is_int_like, "int"),
+                value,
+            );
         }
     }
     pub unsafe fn setFloat(&self, name: &str, value: f32) {
         unsafe {
-            gl::Uniform1f(gl::GetUniformLocation(self.ID, name_to_ptr(name).1), value);
+            gl::Uniform1f(
+                self.uniform_location(name, |k| k == gl::FLOAT, "float"),
+                value,
+            );
         }
     }
     pub unsafe fn setMat4(&self, name: &str, value: glm::TMat4<f32>, transpose: GLboolean) {
         unsafe {
             gl::UniformMatrix4fv(
-                gl::GetUniformLocation(self.ID, name_to_ptr(name).1),
+                self.uniform_location(name, |k| k == gl::FLOAT_MAT4, "mat4"),
                 1,
                 transpose,
                 value.as_ptr(),
@@ -112,7 +477,7 @@ impl Shader {
     ) {
         unsafe {
             gl::Uniform4f(
-                gl::GetUniformLocation(self.ID, name_to_ptr(name).1),
+                self.uniform_location(name, |k| k == gl::FLOAT_VEC4, "vec4"),
                 v0,
                 v1,
                 v2,
@@ -123,7 +488,7 @@ impl Shader {
     pub unsafe fn setVec4(&self, name: &str, value: glm::Vec4) {
         unsafe {
             gl::Uniform4f(
-                gl::GetUniformLocation(self.ID, name_to_ptr(name).1),
+                self.uniform_location(name, |k| k == gl::FLOAT_VEC4, "vec4"),
                 value.x,
                 value.y,
                 value.z,
@@ -134,7 +499,7 @@ impl Shader {
     pub unsafe fn setVec3(&self, name: &str, v: glm::Vec3) {
         unsafe {
             gl::Uniform3f(
-                gl::GetUniformLocation(self.ID, name_to_ptr(name).1),
+                self.uniform_location(name, |k| k == gl::FLOAT_VEC3, "vec3"),
                 v.x,
                 v.y,
                 v.z,
@@ -151,7 +516,7 @@ impl Shader {
     ) {
         unsafe {
             gl::Uniform3f(
-                gl::GetUniformLocation(self.ID, name_to_ptr(name).1),
+                self.uniform_location(name, |k| k == gl::FLOAT_VEC3, "vec3"),
                 v0,
                 v1,
                 v2,
@@ -160,14 +525,8 @@ impl Shader {
     }
 }
 
-fn name_to_ptr(name: &str) -> (CString, *const i8) {
-    let cs =
-        CString::new(name).unwrap_or_else(|_| panic!("Could not convert '{}' to CString", name));
-    let ptr = cs.as_ptr();
-    (cs, ptr)
-}
-
-fn check_shader_compile(shader: u32) {
+/// The info log if `shader` failed to compile.
+fn check_shader_compile(shader: u32) -> Result<(), String> {
     use gl::types::{GLchar, GLint};
     use std::ffi::CStr;
     use std::ptr;
@@ -182,23 +541,24 @@ fn check_shader_compile(shader: u32) {
         unsafe { gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut log_length) };
 
         if log_length > 0 {
-            let mut buffer = Vec::with_capacity(log_length as usize);
-            buffer.extend(std::iter::repeat(b' ' as i8).take(log_length as usize));
+            let mut buffer = vec![b' ' as i8; log_length as usize];
             let error_ptr = buffer.as_mut_ptr() as *mut GLchar;
 
             unsafe { gl::GetShaderInfoLog(shader, log_length, ptr::null_mut(), error_ptr) };
 
             let c_str = unsafe { CStr::from_ptr(error_ptr) };
-            log::error!("Shader compilation failed:\n{}", c_str.to_string_lossy());
+            Err(c_str.to_string_lossy().into_owned())
         } else {
-            log::error!("Shader compilation failed:\n{}", "Unknown Error");
+            Err("Unknown Error".to_string())
         }
     } else {
         log::debug!("Shader compiled successfully");
+        Ok(())
     }
 }
 
-fn check_program_link(program: u32) {
+/// The info log if `program` failed to link.
+fn check_program_link(program: u32) -> Result<(), String> {
     use gl::types::{GLchar, GLint};
     use std::ffi::CStr;
     use std::ptr;
@@ -212,15 +572,18 @@ fn check_program_link(program: u32) {
 
         unsafe { gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut log_length) };
 
-        let mut buffer: Vec<i8> = Vec::with_capacity(log_length as usize);
-        buffer.extend(std::iter::repeat(b' ' as i8).take(log_length as usize));
+        if log_length <= 0 {
+            return Err("Unknown Error".to_string());
+        }
+        let mut buffer = vec![b' ' as i8; log_length as usize];
         let error_ptr = buffer.as_mut_ptr() as *mut GLchar;
 
         unsafe { gl::GetProgramInfoLog(program, log_length, ptr::null_mut(), error_ptr) };
 
         let c_str = unsafe { CStr::from_ptr(error_ptr) };
-        panic!("Program linking failed:\n{}", c_str.to_string_lossy());
+        Err(c_str.to_string_lossy().into_owned())
     } else {
         log::debug!("Program linked successfully");
+        Ok(())
     }
 }
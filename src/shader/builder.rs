@@ -0,0 +1,120 @@
+use super::{Shader, ShaderDefines, ShaderError, ShaderStage, gl_version};
+
+/// The stages of a program before it is built. Any mix of vertex, tessellation, geometry and
+/// fragment stages can be combined, or a compute stage on its own.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderBuilder {
+    stages: Vec<(ShaderStage, String)>,
+    /// Code given with [`ShaderBuilder::source`], by the name used as its path.
+    inline: Vec<(String, String)>,
+    defines: ShaderDefines,
+}
+
+impl ShaderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `stage` from the asset `path`, replacing an earlier file or source for it.
+    pub fn stage(mut self, stage: ShaderStage, path: &str) -> Self {
+        self.stages.retain(|(s, _)| *s != stage);
+        self.stages.push((stage, path.to_string()));
+        self
+    }
+
+    /// Uses `code` for `stage`; includes are still read from the assets.
+    pub fn source(mut self, stage: ShaderStage, code: &str) -> Self {
+        let name = stage.source_name();
+        self.inline.retain(|(n, _)| n != name);
+        self.inline.push((name.to_string(), code.to_string()));
+        self.stage(stage, name)
+    }
+
+    pub fn vertex(self, path: &str) -> Self {
+        self.stage(ShaderStage::Vertex, path)
+    }
+
+    pub fn tess_control(self, path: &str) -> Self {
+        self.stage(ShaderStage::TessControl, path)
+    }
+
+    pub fn tess_evaluation(self, path: &str) -> Self {
+        self.stage(ShaderStage::TessEvaluation, path)
+    }
+
+    pub fn geometry(self, path: &str) -> Self {
+        self.stage(ShaderStage::Geometry, path)
+    }
+
+    pub fn fragment(self, path: &str) -> Self {
+        self.stage(ShaderStage::Fragment, path)
+    }
+
+    pub fn compute(self, path: &str) -> Self {
+        self.stage(ShaderStage::Compute, path)
+    }
+
+    /// `#define`s added to every stage.
+    pub fn defines(mut self, defines: &ShaderDefines) -> Self {
+        self.defines = defines.clone();
+        self
+    }
+
+    pub fn define(mut self, name: &str, value: impl ToString) -> Self {
+        self.defines.set(name, value);
+        self
+    }
+
+    /// Compiles and links the stages on the current context.
+    pub fn build(mut self) -> Result<Shader, ShaderError> {
+        self.stages.sort_by_key(|(stage, _)| *stage);
+        self.validate()?;
+        Shader::build(self.stages, &self.defines, self.inline, None)
+    }
+
+    /// Like [`ShaderBuilder::build`], but logs the error and returns [`Shader::error_shader`]
+    /// in place of the program, still watching the requested files so a fix can be reloaded.
+    pub fn build_or_error_shader(mut self) -> Shader {
+        match self.clone().build() {
+            Ok(shader) => shader,
+            Err(e) => {
+                log::error!("{}", e);
+                self.stages.sort_by_key(|(stage, _)| *stage);
+                Shader::error_fallback(self.stages, &self.defines, self.inline)
+            }
+        }
+    }
+
+    /// Rejects combinations GL can't link, and stages the context is too old for.
+    fn validate(&self) -> Result<(), ShaderError> {
+        let has = |stage| self.stages.iter().any(|(s, _)| *s == stage);
+        let invalid = |reason: &str| {
+            Err(ShaderError::InvalidStages {
+                reason: reason.to_string(),
+            })
+        };
+        if self.stages.is_empty() {
+            return invalid("no stages given");
+        }
+        if has(ShaderStage::Compute) {
+            if self.stages.len() > 1 {
+                return invalid("a compute stage can't be linked with other stages");
+            }
+        } else if !has(ShaderStage::Vertex) {
+            return invalid("a vertex stage is required");
+        } else if has(ShaderStage::TessControl) && !has(ShaderStage::TessEvaluation) {
+            return invalid("a tessellation control stage needs a tessellation evaluation stage");
+        }
+
+        let version = gl_version();
+        for (stage, _) in &self.stages {
+            if version < stage.min_version() {
+                return Err(ShaderError::UnsupportedStage {
+                    stage: *stage,
+                    version,
+                });
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use gl::types::{GLbitfield, GLint};
+
+use super::{Shader, ShaderStage, gl_version};
+
+/// Whether the context can build and dispatch compute programs, core since GL 4.3.
+pub fn compute_supported() -> bool {
+    gl_version() >= ShaderStage::Compute.min_version() && gl::DispatchCompute::is_loaded()
+}
+
+/// Waits for writes from earlier dispatches before the accesses in `barriers`, e.g.
+/// `gl::SHADER_STORAGE_BARRIER_BIT`.
+pub unsafe fn memory_barrier(barriers: GLbitfield) {
+    unsafe { gl::MemoryBarrier(barriers) };
+}
+
+/// Before reading a storage buffer written by an earlier dispatch.
+pub unsafe fn storage_barrier() {
+    unsafe { memory_barrier(gl::SHADER_STORAGE_BARRIER_BIT) };
+}
+
+/// Before reading an image written with `imageStore` by an earlier dispatch.
+pub unsafe fn image_barrier() {
+    unsafe { memory_barrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT) };
+}
+
+/// Before drawing with vertex or index data written by an earlier dispatch, as with particles.
+pub unsafe fn vertex_barrier() {
+    unsafe { memory_barrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT | gl::ELEMENT_ARRAY_BARRIER_BIT) };
+}
+
+impl Shader {
+    /// Whether this is a working compute program. A compute shader that failed to build is not,
+    /// since the error shader standing in for it can only draw.
+    pub fn is_compute(&self) -> bool {
+        !self.is_fallback() && self.stages().any(|stage| stage == ShaderStage::Compute)
+    }
+
+    /// The `local_size` a compute program declares.
+    pub fn work_group_size(&self) -> [u32; 3] {
+        let mut size: [GLint; 3] = [1; 3];
+        if self.is_compute() {
+            unsafe {
+                gl::GetProgramiv(self.ID, gl::COMPUTE_WORK_GROUP_SIZE, size.as_mut_ptr());
+            }
+        }
+        size.map(|n| n.max(1) as u32)
+    }
+
+    /// Runs `x * y * z` work groups of a compute program.
+    pub unsafe fn dispatch(&self, x: u32, y: u32, z: u32) {
+        if !self.is_compute() {
+            let reason = if self.is_fallback() {
+                "it failed to build"
+            } else {
+                "it is not a compute program"
+            };
+            log::error!("Cannot dispatch {}, {}", self.name(), reason);
+            return;
+        }
+        unsafe {
+            gl::UseProgram(self.ID);
+            gl::DispatchCompute(x, y, z);
+        }
+    }
+
+    /// Dispatches enough work groups for at least `items` invocations along each axis.
+    pub unsafe fn dispatch_items(&self, items: [u32; 3]) {
+        let size = self.work_group_size();
+        let groups = [0, 1, 2].map(|i| items[i].div_ceil(size[i]));
+        unsafe { self.dispatch(groups[0], groups[1], groups[2]) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::ptr;
+
+    use glfw::ffi::*;
+
+    use super::*;
+    use crate::shader::ShaderBuilder;
+
+    /// Makes a hidden window's context current and loads GL, which needs a display.
+    unsafe fn make_context() {
+        unsafe {
+            assert_ne!(glfwInit(), 0, "GLFW failed to initialize");
+            glfwWindowHint(VISIBLE, FALSE);
+            let title = CString::new("test").unwrap();
+            let window = glfwCreateWindow(64, 64, title.as_ptr(), ptr::null_mut(), ptr::null_mut());
+            assert!(!window.is_null(), "No OpenGL context");
+            glfwMakeContextCurrent(window);
+            gl::load_with(|name| {
+                let name = CString::new(name).unwrap();
+                glfwGetProcAddress(name.as_ptr()) as *const _
+            });
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a display for an OpenGL context"]
+    fn failed_compute_program_is_not_compute() {
+        unsafe { make_context() };
+        let shader = ShaderBuilder::new()
+            .source(
+                ShaderStage::Compute,
+                "#version 430 core\nlayout(local_size_x = 8) in;\nvoid main() { oops }",
+            )
+            .build_or_error_shader();
+        assert!(shader.is_fallback());
+        assert!(!shader.is_compute());
+        assert_eq!(shader.work_group_size(), [1, 1, 1]);
+        unsafe { shader.dispatch(1, 1, 1) };
+        assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR);
+    }
+}
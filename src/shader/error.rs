@@ -0,0 +1,102 @@
+use std::fmt;
+
+use super::ShaderStage;
+
+/// One diagnostic from a driver's info log, located in the original file where possible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    /// File and 1-based line, when the driver reported a location.
+    pub location: Option<(String, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some((file, line)) => write!(f, "{}:{}: {}", file, line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Why a [`super::Shader`] could not be built.
+#[derive(Clone, Debug)]
+pub enum ShaderError {
+    /// A source or included file that isn't in the assets.
+    MissingAsset {
+        path: String,
+    },
+    /// A source that isn't UTF-8 or contains a NUL byte.
+    InvalidSource {
+        path: String,
+        reason: String,
+    },
+    /// A bad `#include` or `#version` found while preprocessing.
+    Include {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    /// A set of stages that can't form a program.
+    InvalidStages {
+        reason: String,
+    },
+    /// A stage the context's GL version lacks.
+    UnsupportedStage {
+        stage: ShaderStage,
+        version: (i32, i32),
+    },
+    Compile {
+        stage: ShaderStage,
+        path: String,
+        entries: Vec<LogEntry>,
+    },
+    Link {
+        log: String,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::MissingAsset { path } => write!(f, "Shader asset not found: {}", path),
+            ShaderError::InvalidSource { path, reason } => {
+                write!(f, "Invalid shader source {}: {}", path, reason)
+            }
+            ShaderError::Include {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path, line, message),
+            ShaderError::InvalidStages { reason } => write!(f, "Invalid shader stages: {}", reason),
+            ShaderError::UnsupportedStage { stage, version } => {
+                let (major, minor) = stage.min_version();
+                write!(
+                    f,
+                    "{} shaders need GL {}.{}, the context is {}.{}",
+                    stage, major, minor, version.0, version.1
+                )
+            }
+            ShaderError::Compile {
+                stage,
+                path,
+                entries,
+            } => {
+                write!(f, "{} shader {} failed to compile:", stage, path)?;
+                for entry in entries {
+                    write!(f, "\n{}", entry)?;
+                }
+                Ok(())
+            }
+            ShaderError::Link { log } => write!(f, "Program linking failed:\n{}", log.trim_end()),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+impl From<ShaderError> for String {
+    fn from(e: ShaderError) -> Self {
+        e.to_string()
+    }
+}
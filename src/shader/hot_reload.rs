@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use log::{error, info};
+
+use super::{Shader, ShaderError};
+use crate::asset_management::get_assets_dir;
+
+/// Seconds between checks of the watched files.
+const POLL_INTERVAL: f64 = 0.5;
+
+/// Polls the sources of the shaders handed to [`ShaderWatcher::reload`] and recompiles them when
+/// a file changes. A program that fails to compile is kept running, and the log is available
+/// from [`ShaderWatcher::errors`] until a later edit fixes it.
+pub struct ShaderWatcher {
+    /// Asset directory the sources are read from, the installed assets or `src/assets` while
+    /// developing.
+    root: PathBuf,
+    /// Installed assets, which shaders are first built from.
+    installed: PathBuf,
+    /// Modification time of every watched asset, and the generation it last changed in.
+    files: HashMap<String, (Option<SystemTime>, u64)>,
+    /// Bumped whenever a poll finds a changed file.
+    generation: u64,
+    last_poll: f64,
+    /// Compile logs by shader name.
+    errors: BTreeMap<String, String>,
+}
+
+impl ShaderWatcher {
+    pub fn new(root: &Path) -> Self {
+        info!("Watching shaders in {}", root.display());
+        Self {
+            root: root.to_path_buf(),
+            installed: get_assets_dir(),
+            files: HashMap::new(),
+            generation: 1,
+            last_poll: f64::NEG_INFINITY,
+            errors: BTreeMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Checks the watched files for changes, at most every [`POLL_INTERVAL`] seconds of `now`.
+    pub fn poll(&mut self, now: f64) {
+        if now - self.last_poll < POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = now;
+        let mut changed = false;
+        for (path, (modified, generation)) in &mut self.files {
+            let current = modified_time(&self.root.join(path));
+            if current != *modified {
+                *modified = current;
+                *generation = self.generation + 1;
+                changed = true;
+            }
+        }
+        if changed {
+            self.generation += 1;
+        }
+    }
+
+    /// Recompiles `shader` if any of its sources changed since it was last checked, and starts
+    /// watching them otherwise. Returns whether the program was replaced, after which uniforms
+    /// set once at startup need to be set again.
+    pub fn reload(&mut self, shader: &mut Shader) -> bool {
+        // Shaders seen for the first time were just compiled from the installed assets, so they
+        // are only stale if the watched copy differs.
+        let first = shader.generation == 0;
+        let mut changed = false;
+        for path in &shader.sources {
+            let root = &self.root;
+            let (_, generation) = self
+                .files
+                .entry(path.clone())
+                .or_insert_with(|| (modified_time(&root.join(path)), 0));
+            changed |= *generation > shader.generation;
+        }
+        shader.generation = self.generation;
+        if first {
+            changed = shader
+                .sources
+                .iter()
+                .any(|path| self.differs_from_installed(path));
+        }
+        if !changed {
+            return false;
+        }
+
+        let name = shader.name();
+        match shader.reload_from(&self.root) {
+            Ok(()) => {
+                info!("Reloaded {}", name);
+                self.errors.remove(&name);
+                true
+            }
+            Err(e) => {
+                error!(
+                    "Could not reload {}, keeping the last working program: {}",
+                    name, e
+                );
+                self.errors.insert(name, e.to_string());
+                false
+            }
+        }
+    }
+
+    /// Whether the watched `path` differs from the installed one, as while editing `src/assets`.
+    fn differs_from_installed(&self, path: &str) -> bool {
+        self.root != self.installed
+            && std::fs::read(self.installed.join(path)).ok()
+                != std::fs::read(self.root.join(path)).ok()
+    }
+
+    /// Shaders whose latest sources failed to compile, with their logs.
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.errors.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl Shader {
+    /// Compiles the sources under `root` and swaps the new program in, leaving the current one
+    /// in place on failure.
+    pub fn reload_from(&mut self, root: &Path) -> Result<(), ShaderError> {
+        let rebuilt = Shader::build(
+            self.stages.clone(),
+            &self.defines,
+            self.inline.clone(),
+            Some(root),
+        )?;
+        unsafe { gl::DeleteProgram(self.ID) };
+        self.ID = rebuilt.ID;
+        // An edit may have added or removed includes.
+        self.sources = rebuilt.sources;
+        self.reflection = rebuilt.reflection;
+        self.reported.borrow_mut().clear();
+        self.fallback = false;
+        Ok(())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn compares_the_watched_copy_with_the_installed_one() {
+        let dir = std::env::temp_dir().join(format!("hot-reload-{}", std::process::id()));
+        let (installed, watched) = (dir.join("installed"), dir.join("watched"));
+        for root in [&installed, &watched] {
+            fs::create_dir_all(root.join("shaders")).unwrap();
+            fs::write(root.join("shaders/same.vs"), "void main() {}").unwrap();
+        }
+        fs::write(installed.join("shaders/edited.fs"), "old").unwrap();
+        fs::write(watched.join("shaders/edited.fs"), "new").unwrap();
+        fs::write(watched.join("shaders/added.fs"), "new").unwrap();
+
+        let watcher = ShaderWatcher {
+            installed: installed.clone(),
+            ..ShaderWatcher::new(&watched)
+        };
+        let installed_watcher = ShaderWatcher {
+            installed: installed.clone(),
+            ..ShaderWatcher::new(&installed)
+        };
+        let differs = [
+            watcher.differs_from_installed("shaders/same.vs"),
+            watcher.differs_from_installed("shaders/edited.fs"),
+            watcher.differs_from_installed("shaders/added.fs"),
+            installed_watcher.differs_from_installed("shaders/edited.fs"),
+        ];
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(differs, [false, true, true, false]);
+    }
+}
@@ -0,0 +1,411 @@
+use super::error::{LogEntry, ShaderError};
+
+/// Name reported for lines injected from [`ShaderDefines`].
+const DEFINES_FILE: &str = "<defines>";
+
+/// `#define`s added to a shader from Rust, right after its `#version` line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShaderDefines {
+    defines: Vec<(String, String)>,
+}
+
+impl ShaderDefines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces `name`, e.g. `define("NR_POINT_LIGHTS", 4)`.
+    pub fn define(mut self, name: &str, value: impl ToString) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set(&mut self, name: &str, value: impl ToString) {
+        let value = value.to_string();
+        match self.defines.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.defines.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defines.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defines.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+}
+
+/// A shader source with its includes expanded, and where each of its lines came from.
+#[derive(Clone, Debug, Default)]
+pub struct PreprocessedSource {
+    pub code: String,
+    /// Every file read, the root file first.
+    pub files: Vec<String>,
+    /// Index into `files` and 1-based line number of each output line.
+    lines: Vec<(usize, usize)>,
+}
+
+impl PreprocessedSource {
+    /// File and line that output line `line` (1-based) came from.
+    pub fn origin(&self, line: usize) -> Option<(&str, usize)> {
+        let &(file, source_line) = self.lines.get(line.checked_sub(1)?)?;
+        let name = self.files.get(file).map_or(DEFINES_FILE, |f| f.as_str());
+        Some((name, source_line))
+    }
+
+    /// Splits a driver's info log into entries, located in the original files where the driver
+    /// gave a line.
+    pub fn parse_log(&self, log: &str) -> Vec<LogEntry> {
+        log.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let located = find_location(line)
+                    .and_then(|(start, end, n)| Some((start, end, self.origin(n)?)));
+                match located {
+                    Some((start, end, (file, source_line))) => {
+                        let message = format!("{}{}", &line[..start], &line[end..]);
+                        let message = message.trim_start_matches([':', ' ']).trim_start();
+                        LogEntry {
+                            location: Some((file.to_string(), source_line)),
+                            message: message
+                                .strip_prefix("ERROR: ")
+                                .unwrap_or(message)
+                                .to_string(),
+                        }
+                    }
+                    None => LogEntry {
+                        location: None,
+                        message: line.to_string(),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn push(&mut self, line: &str, file: usize, source_line: usize) {
+        self.code.push_str(line);
+        self.code.push('\n');
+        self.lines.push((file, source_line));
+    }
+}
+
+/// Expands `#include "file"` in the asset `path` and injects `defines`. Includes are looked up
+/// next to the including file first, then from the asset root; `read` loads an asset path.
+pub fn preprocess(
+    path: &str,
+    defines: &ShaderDefines,
+    read: &dyn Fn(&str) -> Result<String, ShaderError>,
+) -> Result<PreprocessedSource, ShaderError> {
+    let mut output = PreprocessedSource::default();
+    let mut stack = Vec::new();
+    let mut once = Vec::new();
+    expand(path, defines, read, &mut output, &mut stack, &mut once)?;
+    Ok(output)
+}
+
+fn expand(
+    path: &str,
+    defines: &ShaderDefines,
+    read: &dyn Fn(&str) -> Result<String, ShaderError>,
+    output: &mut PreprocessedSource,
+    stack: &mut Vec<String>,
+    once: &mut Vec<String>,
+) -> Result<(), ShaderError> {
+    if once.iter().any(|p| p == path) {
+        return Ok(());
+    }
+    let source = read(path)?;
+    let file = match output.files.iter().position(|f| f == path) {
+        Some(i) => i,
+        None => {
+            output.files.push(path.to_string());
+            output.files.len() - 1
+        }
+    };
+    let is_root = stack.is_empty();
+    stack.push(path.to_string());
+
+    let has_version = source
+        .lines()
+        .any(|l| l.trim_start().starts_with("#version"));
+    let mut defines_written = !is_root;
+    if is_root && !has_version {
+        write_defines(defines, output);
+        defines_written = true;
+    }
+
+    for (i, line) in source.lines().enumerate() {
+        let number = i + 1;
+        let directive = line.trim_start();
+        if directive.starts_with("#version") {
+            if !is_root {
+                return Err(ShaderError::Include {
+                    path: path.to_string(),
+                    line: number,
+                    message: "#version in an included file".to_string(),
+                });
+            }
+            output.push(line, file, number);
+            if !defines_written {
+                write_defines(defines, output);
+                defines_written = true;
+            }
+        } else if let Some(rest) = directive.strip_prefix("#include") {
+            let include_error = |message: String| ShaderError::Include {
+                path: path.to_string(),
+                line: number,
+                message,
+            };
+            let name = parse_include(rest)
+                .ok_or_else(|| include_error("expected #include \"file\"".to_string()))?;
+            let included = resolve(path, name, read);
+            if stack.contains(&included) {
+                return Err(include_error(format!(
+                    "include cycle: {} -> {}",
+                    stack.join(" -> "),
+                    included
+                )));
+            }
+            expand(&included, defines, read, output, stack, once).map_err(|e| match e {
+                ShaderError::MissingAsset { .. } => {
+                    include_error(format!("included file \"{}\" not found", name))
+                }
+                e => e,
+            })?;
+        } else if directive.starts_with("#pragma once") {
+            once.push(path.to_string());
+        } else {
+            output.push(line, file, number);
+        }
+    }
+
+    stack.pop();
+    Ok(())
+}
+
+fn write_defines(defines: &ShaderDefines, output: &mut PreprocessedSource) {
+    for (i, (name, value)) in defines.iter().enumerate() {
+        output.push(&format!("#define {} {}", name, value), usize::MAX, i + 1);
+    }
+}
+
+fn parse_include(rest: &str) -> Option<&str> {
+    let rest = rest.trim();
+    let name = rest.strip_prefix('"')?.split('"').next()?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// `name` next to `including` if it exists there, otherwise relative to the asset root.
+fn resolve(
+    including: &str,
+    name: &str,
+    read: &dyn Fn(&str) -> Result<String, ShaderError>,
+) -> String {
+    if let Some((dir, _)) = including.rsplit_once('/') {
+        let sibling = format!("{}/{}", dir, name);
+        if read(&sibling).is_ok() {
+            return sibling;
+        }
+    }
+    name.to_string()
+}
+
+/// Byte range and line number of the first `0:LINE` or `0(LINE)` in a log line, the forms used
+/// by Mesa, NVIDIA and AMD for source string 0. The range covers Mesa's `(COLUMN)` after the line.
+fn find_location(line: &str) -> Option<(usize, usize, usize)> {
+    let bytes = line.as_bytes();
+    for start in 0..bytes.len() {
+        if bytes[start] != b'0' || (start > 0 && bytes[start - 1].is_ascii_digit()) {
+            continue;
+        }
+        let (open, close) = match bytes.get(start + 1) {
+            Some(b':') => (start + 2, None),
+            Some(b'(') => (start + 2, Some(b')')),
+            _ => continue,
+        };
+        let digits = bytes[open..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            continue;
+        }
+        let mut end = open + digits;
+        if let Some(close) = close {
+            if bytes.get(end) != Some(&close) {
+                continue;
+            }
+            end += 1;
+        } else if bytes.get(end) == Some(&b'(') {
+            let column = bytes[end + 1..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            if column > 0 && bytes.get(end + 1 + column) == Some(&b')') {
+                end += column + 2;
+            }
+        }
+        let number = line[open..open + digits].parse().ok()?;
+        return Some((start, end, number));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(
+        files: &[(&str, &str)],
+        defines: &ShaderDefines,
+    ) -> Result<PreprocessedSource, ShaderError> {
+        let read = |path: &str| match files.iter().find(|(name, _)| *name == path) {
+            Some((_, code)) => Ok(code.to_string()),
+            None => Err(ShaderError::MissingAsset {
+                path: path.to_string(),
+            }),
+        };
+        preprocess(files[0].0, defines, &read)
+    }
+
+    #[test]
+    fn defines_follow_the_version_line() {
+        let defines = ShaderDefines::new().define("LIGHTS", 4);
+        let source = run(
+            &[("a.frag", "// header\n#version 330 core\nvoid main() {}")],
+            &defines,
+        )
+        .unwrap();
+        let lines: Vec<_> = source.code.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "// header",
+                "#version 330 core",
+                "#define LIGHTS 4",
+                "void main() {}"
+            ]
+        );
+        assert_eq!(source.origin(3), Some((DEFINES_FILE, 1)));
+        assert_eq!(source.origin(4), Some(("a.frag", 3)));
+    }
+
+    #[test]
+    fn includes_map_back_to_their_files() {
+        let source = run(
+            &[
+                (
+                    "shaders/a.frag",
+                    "#version 330 core\n#include \"common.glsl\"\nvoid main() {}",
+                ),
+                ("shaders/common.glsl", "float x;\nfloat y;"),
+            ],
+            &ShaderDefines::new(),
+        )
+        .unwrap();
+        assert_eq!(source.files, ["shaders/a.frag", "shaders/common.glsl"]);
+        assert_eq!(source.origin(3), Some(("shaders/common.glsl", 2)));
+        assert_eq!(source.origin(4), Some(("shaders/a.frag", 3)));
+    }
+
+    #[test]
+    fn pragma_once_includes_a_file_once() {
+        let source = run(
+            &[
+                ("a.frag", "#include \"b.glsl\"\n#include \"b.glsl\""),
+                ("b.glsl", "#pragma once\nfloat b;"),
+            ],
+            &ShaderDefines::new(),
+        )
+        .unwrap();
+        assert_eq!(source.code, "float b;\n");
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        let error = run(
+            &[
+                ("a.frag", "#include \"b.glsl\""),
+                ("b.glsl", "#include \"a.frag\""),
+            ],
+            &ShaderDefines::new(),
+        )
+        .unwrap_err();
+        match error {
+            ShaderError::Include {
+                path,
+                line,
+                message,
+            } => {
+                assert_eq!((path.as_str(), line), ("b.glsl", 1));
+                assert_eq!(message, "include cycle: a.frag -> b.glsl -> a.frag");
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn missing_includes_are_errors() {
+        let error = run(
+            &[("a.frag", "\n#include \"gone.glsl\"")],
+            &ShaderDefines::new(),
+        );
+        assert!(matches!(error, Err(ShaderError::Include { line: 2, .. })));
+    }
+
+    #[test]
+    fn finds_mesa_and_nvidia_locations() {
+        assert_eq!(
+            find_location("0:12(5): error: `x' undeclared"),
+            Some((0, 7, 12))
+        );
+        assert_eq!(
+            find_location("0(12) : error C1008: undefined"),
+            Some((0, 5, 12))
+        );
+        assert_eq!(
+            find_location("ERROR: 0:7: 'x' : undeclared"),
+            Some((7, 10, 7))
+        );
+        assert_eq!(find_location("10:3 is not source string 0"), None);
+        assert_eq!(find_location("link error"), None);
+    }
+
+    #[test]
+    fn parses_logs_into_original_locations() {
+        let source = run(
+            &[
+                (
+                    "a.frag",
+                    "#version 330 core\n#include \"b.glsl\"\nvoid main() {}",
+                ),
+                ("b.glsl", "float b;"),
+            ],
+            &ShaderDefines::new().define("A", 1),
+        )
+        .unwrap();
+        let entries = source.parse_log(
+            "0:3(1): error: syntax error\n\n0(4) : error C0000: bad main\nwarning without a line",
+        );
+        assert_eq!(
+            entries,
+            [
+                LogEntry {
+                    location: Some(("b.glsl".to_string(), 1)),
+                    message: "error: syntax error".to_string(),
+                },
+                LogEntry {
+                    location: Some(("a.frag".to_string(), 3)),
+                    message: "error C0000: bad main".to_string(),
+                },
+                LogEntry {
+                    location: None,
+                    message: "warning without a line".to_string(),
+                },
+            ]
+        );
+    }
+}
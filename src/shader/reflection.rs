@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use gl::types::{GLchar, GLenum, GLint, GLuint};
+
+/// An active uniform of a linked program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformInfo {
+    pub name: String,
+    pub location: GLint,
+    /// GL type, e.g. `gl::FLOAT_VEC3`.
+    pub kind: GLenum,
+    /// Number of elements for arrays, 1 otherwise.
+    pub size: GLint,
+}
+
+/// An active vertex attribute of a linked program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeInfo {
+    pub name: String,
+    pub location: GLint,
+    pub kind: GLenum,
+    pub size: GLint,
+}
+
+/// The uniforms and attributes a program kept after linking, with their locations, so setting a
+/// uniform is a map lookup instead of a `glGetUniformLocation` call.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderReflection {
+    uniforms: HashMap<String, UniformInfo>,
+    attributes: Vec<AttributeInfo>,
+}
+
+impl ShaderReflection {
+    /// Enumerates the active uniforms and attributes of the linked `program`.
+    pub fn query(program: GLuint) -> Self {
+        let mut reflection = Self::default();
+        unsafe {
+            let (mut count, mut max_length) = (0, 0);
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+            let mut buffer = vec![0u8; max_length.max(1) as usize];
+            for index in 0..count as GLuint {
+                let (name, kind, size) =
+                    active_variable(program, index, &mut buffer, gl::GetActiveUniform);
+                let location = uniform_location(program, &name);
+                // Arrays report their first element, e.g. `lights[0]`. The GL doesn't promise
+                // consecutive locations for the other elements, so each one is looked up.
+                let base = name.strip_suffix("[0]").unwrap_or(&name).to_string();
+                for element in 1..size {
+                    let element_name = format!("{}[{}]", base, element);
+                    reflection.insert(UniformInfo {
+                        location: uniform_location(program, &element_name),
+                        name: element_name,
+                        kind,
+                        size: 1,
+                    });
+                }
+                if base != name {
+                    reflection.insert(UniformInfo {
+                        name: name.clone(),
+                        location,
+                        kind,
+                        size: 1,
+                    });
+                }
+                reflection.insert(UniformInfo {
+                    name: base,
+                    location,
+                    kind,
+                    size,
+                });
+            }
+
+            gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+            gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_length);
+            buffer.resize(max_length.max(1) as usize, 0);
+            for index in 0..count as GLuint {
+                let (name, kind, size) =
+                    active_variable(program, index, &mut buffer, gl::GetActiveAttrib);
+                let c_name = crate::cstr!(name.as_str());
+                let location = gl::GetAttribLocation(program, c_name.as_ptr());
+                reflection.attributes.push(AttributeInfo {
+                    name,
+                    location,
+                    kind,
+                    size,
+                });
+            }
+        }
+        reflection.attributes.sort_by_key(|a| a.location);
+        reflection
+    }
+
+    fn insert(&mut self, uniform: UniformInfo) {
+        self.uniforms.insert(uniform.name.clone(), uniform);
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.get(name)
+    }
+
+    /// Every uniform by name, array elements included.
+    pub fn uniforms(&self) -> impl Iterator<Item = &UniformInfo> {
+        self.uniforms.values()
+    }
+
+    /// Attributes ordered by location.
+    pub fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+}
+
+type ActiveVariableFn =
+    unsafe fn(GLuint, GLuint, i32, *mut i32, *mut GLint, *mut GLenum, *mut GLchar);
+
+/// Reads the name, type and size of an active variable into `buffer`, which must hold the
+/// program's longest name including the terminator.
+unsafe fn active_variable(
+    program: GLuint,
+    index: GLuint,
+    buffer: &mut [u8],
+    query: ActiveVariableFn,
+) -> (String, GLenum, GLint) {
+    let (mut length, mut size, mut kind) = (0, 0, 0);
+    unsafe {
+        query(
+            program,
+            index,
+            buffer.len() as i32,
+            &mut length,
+            &mut size,
+            &mut kind,
+            buffer.as_mut_ptr() as *mut GLchar,
+        )
+    };
+    let name = String::from_utf8_lossy(&buffer[..length.max(0) as usize]).into_owned();
+    (name, kind, size)
+}
+
+unsafe fn uniform_location(program: GLuint, name: &str) -> GLint {
+    let c_name = crate::cstr!(name);
+    unsafe { gl::GetUniformLocation(program, c_name.as_ptr()) }
+}
+
+/// GLSL name of a uniform or attribute type, for messages.
+pub fn type_name(kind: GLenum) -> &'static str {
+    match kind {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::BOOL => "bool",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        _ => "unknown",
+    }
+}
+
+/// Whether `glUniform1i` can set a uniform of type `kind`, which includes bools and samplers.
+pub fn is_int_like(kind: GLenum) -> bool {
+    matches!(
+        kind,
+        gl::INT
+            | gl::BOOL
+            | gl::SAMPLER_1D
+            | gl::SAMPLER_2D
+            | gl::SAMPLER_3D
+            | gl::SAMPLER_CUBE
+            | gl::SAMPLER_2D_SHADOW
+            | gl::SAMPLER_2D_ARRAY
+            | gl::SAMPLER_2D_MULTISAMPLE
+            | gl::INT_SAMPLER_2D
+            | gl::UNSIGNED_INT_SAMPLER_2D
+    )
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+use log::debug;
+
+use super::{Shader, ShaderConstructorDefines, ShaderDefines};
+
+/// Optional shader features, each compiled in with a `#define` instead of a runtime branch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderFeatures(u32);
+
+impl ShaderFeatures {
+    pub const NONE: ShaderFeatures = ShaderFeatures(0);
+    /// Parallax mapping from a height map.
+    pub const HEIGHT_MAPPING: ShaderFeatures = ShaderFeatures(1 << 0);
+    /// Tangent space normals from a normal map.
+    pub const NORMAL_MAPPING: ShaderFeatures = ShaderFeatures(1 << 1);
+
+    /// Every feature with the name of its define.
+    const NAMES: [(ShaderFeatures, &'static str); 2] = [
+        (Self::HEIGHT_MAPPING, "HEIGHT_MAPPING"),
+        (Self::NORMAL_MAPPING, "NORMAL_MAPPING"),
+    ];
+
+    pub fn contains(&self, other: ShaderFeatures) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn set(&mut self, feature: ShaderFeatures, enabled: bool) {
+        if enabled {
+            self.0 |= feature.0;
+        } else {
+            self.0 &= !feature.0;
+        }
+    }
+
+    /// Define names of the features set.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        Self::NAMES
+            .iter()
+            .filter(|(feature, _)| self.contains(*feature))
+            .map(|(_, name)| *name)
+    }
+}
+
+impl BitOr for ShaderFeatures {
+    type Output = ShaderFeatures;
+
+    fn bitor(self, rhs: Self) -> Self {
+        ShaderFeatures(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ShaderFeatures {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for ShaderFeatures {
+    type Output = ShaderFeatures;
+
+    fn bitand(self, rhs: Self) -> Self {
+        ShaderFeatures(self.0 & rhs.0)
+    }
+}
+
+impl fmt::Display for ShaderFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no features");
+        }
+        let names: Vec<_> = self.names().collect();
+        write!(f, "{}", names.join(" + "))
+    }
+}
+
+/// One program per combination of [`ShaderFeatures`] of a base shader. Variants are compiled by
+/// [`ShaderVariants::prepare`], outside of drawing, so the caller can set their uniforms first.
+pub struct ShaderVariants {
+    vertexPath: String,
+    fragmentPath: String,
+    defines: ShaderDefines,
+    /// Features the sources implement; others are dropped from requests so they don't compile
+    /// identical programs.
+    supported: ShaderFeatures,
+    variants: HashMap<ShaderFeatures, Shader>,
+}
+
+impl ShaderVariants {
+    pub fn new(
+        vertexPath: &str,
+        fragmentPath: &str,
+        defines: &ShaderDefines,
+        supported: ShaderFeatures,
+    ) -> Self {
+        Self {
+            vertexPath: vertexPath.to_string(),
+            fragmentPath: fragmentPath.to_string(),
+            defines: defines.clone(),
+            supported,
+            variants: HashMap::new(),
+        }
+    }
+
+    pub fn supported(&self) -> ShaderFeatures {
+        self.supported
+    }
+
+    /// The program for `features`, if [`ShaderVariants::prepare`] compiled it.
+    pub fn get(&self, features: ShaderFeatures) -> Option<&Shader> {
+        self.variants.get(&(features & self.supported))
+    }
+
+    /// Compiles the variant for `features` unless it exists. Returns whether it was compiled
+    /// now, in which case it still needs every uniform set.
+    pub fn prepare(&mut self, features: ShaderFeatures) -> bool {
+        let features = features & self.supported;
+        if self.variants.contains_key(&features) {
+            return false;
+        }
+        debug!(
+            "Compiling {} + {} with {}",
+            self.vertexPath, self.fragmentPath, features
+        );
+        let mut defines = self.defines.clone();
+        for name in features.names() {
+            defines.set(name, 1);
+        }
+        let shader = ShaderConstructorDefines(&self.vertexPath, &self.fragmentPath, &defines);
+        self.variants.insert(features, shader);
+        true
+    }
+
+    /// Every compiled variant.
+    pub fn iter(&self) -> impl Iterator<Item = (ShaderFeatures, &Shader)> {
+        self.variants
+            .iter()
+            .map(|(features, shader)| (*features, shader))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ShaderFeatures, &mut Shader)> {
+        self.variants
+            .iter_mut()
+            .map(|(features, shader)| (*features, shader))
+    }
+}
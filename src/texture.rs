@@ -1,6 +1,5 @@
 #![allow(non_snake_case, non_camel_case_types, dead_code)]
 use gl::{self, types::GLenum};
-use glfw::ffi::TRUE;
 use stb_image;
 
 #[repr(C)]
@@ -89,7 +88,7 @@ pub unsafe fn TextureConstructor(
         let loc = crate::asset_management::get_asset_path_cstr(path.as_str())
             .expect("Could not get asset path");
         if flip {
-            stb_image::stb_image::stbi_set_flip_vertically_on_load(TRUE);
+            crate::util::image::set_flip_on_load(true);
         }
         let data: *mut std::ffi::c_void = stb_image::stb_image::stbi_load(
             loc.as_ptr(),
@@ -1,13 +1,25 @@
 use std::{
     fs::File,
-    io::{Read, Seek, SeekFrom},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 pub mod glmaddon;
-
-pub fn init_logging() {
-    log4rs::init_file("log4rs.yml", Default::default()).unwrap();
+pub mod image;
+
+/// Configures logging from `log4rs.yml`, optionally overriding its root level.
+pub fn init_logging(level: Option<log::LevelFilter>) {
+    match level {
+        Some(level) => {
+            let mut config =
+                log4rs::config::load_config_file("log4rs.yml", Default::default()).unwrap();
+            config.root_mut().set_level(level);
+            log4rs::init_config(config).unwrap();
+        }
+        None => {
+            log4rs::init_file("log4rs.yml", Default::default()).unwrap();
+        }
+    }
     log::debug!("log4rs configured!");
 }
 
@@ -25,24 +37,36 @@ impl std::process::Termination for LinuxExitCode {
     }
 }
 
+/// Big-endian reads from a buffered file. Reads past the end return zeros.
 pub struct BinaryReader {
     position: u64,
-    f: File,
+    f: BufReader<File>,
 }
 
 impl BinaryReader {
     pub fn new(file: File) -> BinaryReader {
         Self {
             position: 0,
-            f: file,
+            f: BufReader::new(file),
+        }
+    }
+
+    /// Fills `buf` from the current position, zeroing whatever is past the end of the file.
+    fn read_into(&mut self, buf: &mut [u8]) {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.f.read(&mut buf[filled..]) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => filled += n,
+            }
         }
+        buf[filled..].fill(0);
+        self.position += buf.len() as u64;
     }
 
     pub fn get_uint8(&mut self) -> u8 {
         let mut buf = [0u8; 1];
-        self.f.seek(SeekFrom::Start(self.position)).unwrap();
-        self.f.read(&mut buf).unwrap();
-        self.position += 1;
+        self.read_into(&mut buf);
         buf[0]
     }
 
@@ -64,6 +88,20 @@ impl BinaryReader {
         self.get_uint16() as i16
     }
 
+    pub fn get_uint64(&mut self) -> u64 {
+        let hi = self.get_uint32() as u64;
+        let lo = self.get_uint32() as u64;
+        (hi << 32) | lo
+    }
+
+    pub fn get_f32(&mut self) -> f32 {
+        f32::from_bits(self.get_uint32())
+    }
+
+    pub fn get_f64(&mut self) -> f64 {
+        f64::from_bits(self.get_uint64())
+    }
+
     pub fn get_int32(&mut self) -> i32 {
         let b1 = self.get_uint8() as u32;
         let b2 = self.get_uint8() as u32;
@@ -98,9 +136,7 @@ impl BinaryReader {
 
     pub fn get_string(&mut self, length: usize) -> String {
         let mut buf = vec![0u8; length];
-        self.f.seek(SeekFrom::Start(self.position)).unwrap();
-        self.f.read(&mut buf).unwrap();
-        self.position += length as u64;
+        self.read_into(&mut buf);
         String::from_utf8_lossy(&buf).into_owned()
     }
 
@@ -123,6 +159,69 @@ impl BinaryReader {
     }
 
     pub fn set_position(&mut self, position: u64) {
-        self.position = position
+        if position != self.position && self.f.seek(SeekFrom::Start(position)).is_ok() {
+            self.position = position;
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.f.get_ref().metadata().map(|m| m.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Big-endian counterpart of [`BinaryReader`].
+pub struct BinaryWriter {
+    f: BufWriter<File>,
+}
+
+impl BinaryWriter {
+    pub fn new(file: File) -> BinaryWriter {
+        Self {
+            f: BufWriter::new(file),
+        }
+    }
+
+    pub fn put_uint8(&mut self, v: u8) -> io::Result<()> {
+        self.f.write_all(&[v])
+    }
+
+    pub fn put_uint16(&mut self, v: u16) -> io::Result<()> {
+        self.f.write_all(&v.to_be_bytes())
+    }
+
+    pub fn put_uint32(&mut self, v: u32) -> io::Result<()> {
+        self.f.write_all(&v.to_be_bytes())
+    }
+
+    pub fn put_uint64(&mut self, v: u64) -> io::Result<()> {
+        self.f.write_all(&v.to_be_bytes())
+    }
+
+    pub fn put_int16(&mut self, v: i16) -> io::Result<()> {
+        self.put_uint16(v as u16)
+    }
+
+    pub fn put_int32(&mut self, v: i32) -> io::Result<()> {
+        self.put_uint32(v as u32)
+    }
+
+    pub fn put_f32(&mut self, v: f32) -> io::Result<()> {
+        self.put_uint32(v.to_bits())
+    }
+
+    pub fn put_f64(&mut self, v: f64) -> io::Result<()> {
+        self.put_uint64(v.to_bits())
+    }
+
+    pub fn put_string(&mut self, s: &str) -> io::Result<()> {
+        self.f.write_all(s.as_bytes())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.f.flush()
     }
 }
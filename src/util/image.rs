@@ -0,0 +1,199 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use flate2::Compression;
+use flate2::Crc;
+use flate2::write::ZlibEncoder;
+
+/// Mirror of stb_image's global flip flag, which it has no getter for.
+static FLIP_ON_LOAD: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether stb_image flips loaded images vertically, returning the previous setting.
+pub fn set_flip_on_load(flip: bool) -> bool {
+    unsafe { stb_image::stb_image::stbi_set_flip_vertically_on_load(flip as i32) };
+    FLIP_ON_LOAD.swap(flip, Ordering::Relaxed)
+}
+
+/// An 8-bit RGBA image stored top row first.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Result of comparing two images with [`Image::diff`].
+#[derive(Debug, Clone, Copy)]
+pub struct ImageDiff {
+    pub total_pixels: usize,
+    pub mismatched_pixels: usize,
+    pub max_delta: u8,
+}
+
+impl ImageDiff {
+    pub fn passed(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let loc = CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|_| format!("Invalid image path: {}", path.display()))?;
+        let (mut width, mut height, mut channels) = (0i32, 0i32, 0i32);
+        let flip = set_flip_on_load(false);
+        unsafe {
+            let data = stb_image::stb_image::stbi_load(
+                loc.as_ptr(),
+                &mut width,
+                &mut height,
+                &mut channels,
+                4,
+            );
+            set_flip_on_load(flip);
+            if data.is_null() {
+                return Err(format!("Failed to load image: {}", path.display()));
+            }
+            let pixels = std::slice::from_raw_parts(data, (width * height * 4) as usize).to_vec();
+            stb_image::stb_image::stbi_image_free(data as *mut _);
+            Ok(Self::new(width as u32, height as u32, pixels))
+        }
+    }
+
+    pub fn write_png(&self, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&self.width.to_be_bytes());
+        ihdr.extend_from_slice(&self.height.to_be_bytes());
+        // 8 bits per channel, color type 6 (RGBA), default compression/filter, no interlace
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+        write_chunk(&mut file, b"IHDR", &ihdr)?;
+
+        let row = self.width as usize * 4;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for line in self.pixels.chunks_exact(row) {
+            // filter type 0 (none) for every scanline
+            encoder.write_all(&[0])?;
+            encoder.write_all(line)?;
+        }
+        write_chunk(&mut file, b"IDAT", &encoder.finish()?)?;
+        write_chunk(&mut file, b"IEND", &[])?;
+        file.flush()
+    }
+
+    /// Compares every channel of every pixel; a pixel mismatches when any channel differs by more
+    /// than `tolerance`.
+    pub fn diff(&self, other: &Image, tolerance: u8) -> Result<ImageDiff, String> {
+        if self.width != other.width || self.height != other.height {
+            return Err(format!(
+                "Image sizes differ: {}x{} vs {}x{}",
+                self.width, self.height, other.width, other.height
+            ));
+        }
+        let mut result = ImageDiff {
+            total_pixels: (self.width * self.height) as usize,
+            mismatched_pixels: 0,
+            max_delta: 0,
+        };
+        for (a, b) in self
+            .pixels
+            .chunks_exact(4)
+            .zip(other.pixels.chunks_exact(4))
+        {
+            let delta = a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| x.abs_diff(*y))
+                .max()
+                .unwrap_or(0);
+            result.max_delta = result.max_delta.max(delta);
+            if delta > tolerance {
+                result.mismatched_pixels += 1;
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    out.write_all(&crc.sum().to_be_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> Image {
+        let pixels = (0..width * height)
+            .flat_map(|i| [(i * 16) as u8, (i * 8) as u8, 255 - i as u8, 255])
+            .collect();
+        Image::new(width, height, pixels)
+    }
+
+    #[test]
+    fn identical_images_pass() {
+        let image = gradient(4, 3);
+        let diff = image.diff(&image.clone(), 0).unwrap();
+        assert!(diff.passed());
+        assert_eq!(diff.total_pixels, 12);
+        assert_eq!(diff.max_delta, 0);
+    }
+
+    #[test]
+    fn differences_within_tolerance_pass() {
+        let image = gradient(4, 3);
+        let mut other = image.clone();
+        other.pixels[1] = other.pixels[1].wrapping_add(2);
+        other.pixels[9] = other.pixels[9].wrapping_sub(3);
+
+        let diff = image.diff(&other, 3).unwrap();
+        assert!(diff.passed());
+        assert_eq!(diff.max_delta, 3);
+
+        let diff = image.diff(&other, 2).unwrap();
+        assert!(!diff.passed());
+        assert_eq!(diff.mismatched_pixels, 1);
+    }
+
+    #[test]
+    fn size_mismatch_is_an_error() {
+        assert!(gradient(4, 3).diff(&gradient(3, 4), 255).is_err());
+    }
+
+    #[test]
+    fn write_png_round_trips_and_load_keeps_the_flip_flag() {
+        let image = gradient(5, 2);
+        let path = std::env::temp_dir().join(format!("image-test-{}.png", std::process::id()));
+        image.write_png(&path).unwrap();
+        set_flip_on_load(true);
+        let loaded = Image::load(&path);
+        let flip = set_flip_on_load(false);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(flip);
+
+        let loaded = loaded.unwrap();
+        assert_eq!((loaded.width, loaded.height), (5, 2));
+        assert_eq!(loaded.pixels, image.pixels);
+    }
+}
@@ -0,0 +1,517 @@
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+use glfw::ffi::*;
+use log::{error, info, warn};
+use serde::Deserialize;
+
+use crate::asset_management::get_asset_path;
+use crate::settings::WindowSettings;
+use crate::util::image::Image;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum FullscreenMode {
+    #[default]
+    Windowed,
+    /// An undecorated window covering the monitor at its current video mode.
+    Borderless,
+    /// Takes over the monitor and switches it to the requested video mode.
+    Exclusive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+    pub width: i32,
+    pub height: i32,
+    pub refresh_rate: i32,
+}
+
+impl From<&GLFWvidmode> for VideoMode {
+    fn from(mode: &GLFWvidmode) -> Self {
+        Self {
+            width: mode.width,
+            height: mode.height,
+            refresh_rate: mode.refreshRate,
+        }
+    }
+}
+
+/// A connected monitor. Only valid until it is disconnected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Monitor {
+    handle: *mut GLFWmonitor,
+}
+
+impl Monitor {
+    /// Every connected monitor, the primary one first.
+    pub fn all() -> Vec<Monitor> {
+        let mut count = 0;
+        unsafe {
+            let monitors = glfwGetMonitors(&mut count);
+            if monitors.is_null() {
+                return Vec::new();
+            }
+            std::slice::from_raw_parts(monitors, count as usize)
+                .iter()
+                .map(|&handle| Monitor { handle })
+                .collect()
+        }
+    }
+
+    pub fn primary() -> Option<Monitor> {
+        let handle = unsafe { glfwGetPrimaryMonitor() };
+        (!handle.is_null()).then_some(Monitor { handle })
+    }
+
+    pub fn name(&self) -> String {
+        unsafe {
+            let name = glfwGetMonitorName(self.handle);
+            if name.is_null() {
+                "Unknown monitor".to_string()
+            } else {
+                CStr::from_ptr(name).to_string_lossy().into_owned()
+            }
+        }
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        let (mut x, mut y) = (0, 0);
+        unsafe { glfwGetMonitorPos(self.handle, &mut x, &mut y) };
+        (x, y)
+    }
+
+    /// The mode the monitor is currently running at.
+    pub fn video_mode(&self) -> Option<VideoMode> {
+        unsafe { glfwGetVideoMode(self.handle).as_ref() }.map(VideoMode::from)
+    }
+
+    /// Supported modes, sorted from smallest to largest as GLFW reports them.
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        let mut count = 0;
+        unsafe {
+            let modes = glfwGetVideoModes(self.handle, &mut count);
+            if modes.is_null() {
+                return Vec::new();
+            }
+            std::slice::from_raw_parts(modes, count as usize)
+                .iter()
+                .map(VideoMode::from)
+                .collect()
+        }
+    }
+
+    /// The supported mode with the given size, preferring `refresh_rate` if given and the
+    /// highest refresh rate otherwise.
+    pub fn find_video_mode(
+        &self,
+        width: i32,
+        height: i32,
+        refresh_rate: Option<i32>,
+    ) -> Option<VideoMode> {
+        let sized: Vec<VideoMode> = self
+            .video_modes()
+            .into_iter()
+            .filter(|m| m.width == width && m.height == height)
+            .collect();
+        refresh_rate
+            .and_then(|rate| sized.iter().find(|m| m.refresh_rate == rate))
+            .or_else(|| sized.iter().max_by_key(|m| m.refresh_rate))
+            .copied()
+    }
+
+    #[allow(dead_code)]
+    pub fn content_scale(&self) -> (f32, f32) {
+        let (mut x, mut y) = (1f32, 1f32);
+        unsafe { glfwGetMonitorContentScale(self.handle, &mut x, &mut y) };
+        (x, y)
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    Arrow,
+    IBeam,
+    Crosshair,
+    Hand,
+    HResize,
+    VResize,
+}
+
+impl CursorShape {
+    fn glfw_shape(self) -> i32 {
+        match self {
+            CursorShape::Arrow => ARROW_CURSOR,
+            CursorShape::IBeam => IBEAM_CURSOR,
+            CursorShape::Crosshair => CROSSHAIR_CURSOR,
+            CursorShape::Hand => HAND_CURSOR,
+            CursorShape::HResize => HRESIZE_CURSOR,
+            CursorShape::VResize => VRESIZE_CURSOR,
+        }
+    }
+}
+
+/// The GLFW window and its OpenGL context, plus the display state around it: fullscreen mode,
+/// vsync, icon, cursor and the framebuffer size and content scale it currently has.
+pub struct Window {
+    handle: *mut GLFWwindow,
+    fullscreen: FullscreenMode,
+    /// The mode [`Window::toggle_fullscreen`] switches to from windowed.
+    preferred_fullscreen: FullscreenMode,
+    monitor: Option<Monitor>,
+    /// Position and size to restore when leaving fullscreen.
+    windowed_rect: (i32, i32, i32, i32),
+    vsync: bool,
+    samples: u32,
+    framebuffer_size: (i32, i32),
+    content_scale: (f32, f32),
+    cursor: *mut GLFWcursor,
+}
+
+impl Window {
+    /// Creates the window (hidden) and makes its context current. GLFW must be initialized.
+    pub fn new(settings: &WindowSettings) -> Result<Self, String> {
+        let version = settings.gl_version;
+        let title = CString::new(settings.title.as_str()).unwrap();
+        let handle = unsafe {
+            glfwDefaultWindowHints();
+            glfwWindowHint(CONTEXT_VERSION_MAJOR, version.major);
+            glfwWindowHint(CONTEXT_VERSION_MINOR, version.minor);
+            if (version.major, version.minor) >= (3, 2) {
+                glfwWindowHint(OPENGL_PROFILE, OPENGL_COMPAT_PROFILE);
+            }
+            if version.major >= 3 {
+                glfwWindowHint(OPENGL_FORWARD_COMPAT, TRUE);
+            }
+            glfwWindowHint(SAMPLES, settings.msaa as i32);
+            glfwWindowHint(SCALE_TO_MONITOR, TRUE);
+            glfwWindowHint(VISIBLE, FALSE);
+            glfwCreateWindow(
+                settings.width,
+                settings.height,
+                title.as_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if handle.is_null() {
+            error!("Failed to create GLFW window with OpenGL {}!", version);
+            return Err(format!(
+                "Failed to create GLFW window with OpenGL {}!",
+                version
+            ));
+        }
+        unsafe { glfwMakeContextCurrent(handle) };
+
+        let monitor = match settings.monitor {
+            Some(i) => Monitor::all().get(i).copied().or_else(|| {
+                warn!("Monitor {} not found, using the primary monitor", i);
+                Monitor::primary()
+            }),
+            None => Monitor::primary(),
+        };
+        let mut window = Self {
+            handle,
+            fullscreen: FullscreenMode::Windowed,
+            preferred_fullscreen: match settings.fullscreen {
+                FullscreenMode::Windowed => FullscreenMode::Borderless,
+                mode => mode,
+            },
+            monitor,
+            windowed_rect: (0, 0, settings.width, settings.height),
+            vsync: settings.vsync,
+            samples: settings.msaa,
+            framebuffer_size: (0, 0),
+            content_scale: (1f32, 1f32),
+            cursor: ptr::null_mut(),
+        };
+        window.set_vsync(settings.vsync);
+        if settings.fullscreen != FullscreenMode::Windowed {
+            window.set_fullscreen(settings.fullscreen, settings.refresh_rate);
+        }
+        unsafe {
+            let (mut width, mut height) = (0, 0);
+            glfwGetFramebufferSize(handle, &mut width, &mut height);
+            window.framebuffer_size = (width, height);
+            let (mut x, mut y) = (1f32, 1f32);
+            glfwGetWindowContentScale(handle, &mut x, &mut y);
+            window.content_scale = (x, y);
+        }
+        if let Some(icon) = &settings.icon
+            && let Err(e) = window.set_icon(icon)
+        {
+            warn!("{}", e);
+        }
+        Ok(window)
+    }
+
+    pub fn handle(&self) -> *mut GLFWwindow {
+        self.handle
+    }
+
+    pub fn show(&self) {
+        unsafe { glfwShowWindow(self.handle) };
+    }
+
+    pub fn should_close(&self) -> bool {
+        unsafe { glfwWindowShouldClose(self.handle) != 0 }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_should_close(&self, close: bool) {
+        unsafe { glfwSetWindowShouldClose(self.handle, close as i32) };
+    }
+
+    pub fn swap_buffers(&self) {
+        unsafe { glfwSwapBuffers(self.handle) };
+    }
+
+    pub fn set_title(&self, title: &str) {
+        let title = CString::new(title).unwrap();
+        unsafe { glfwSetWindowTitle(self.handle, title.as_ptr()) };
+    }
+
+    /// Size in screen coordinates; see [`Window::framebuffer_size`] for pixels.
+    pub fn size(&self) -> (i32, i32) {
+        let (mut width, mut height) = (0, 0);
+        unsafe { glfwGetWindowSize(self.handle, &mut width, &mut height) };
+        (width, height)
+    }
+
+    /// Cursor position in screen coordinates from the top left of the content area.
+    pub fn cursor_position(&self) -> (f64, f64) {
+        let (mut x, mut y) = (0f64, 0f64);
+        unsafe { glfwGetCursorPos(self.handle, &mut x, &mut y) };
+        (x, y)
+    }
+
+    /// Size of the default framebuffer in pixels, which differs from [`Window::size`] on HiDPI
+    /// displays.
+    pub fn framebuffer_size(&self) -> (i32, i32) {
+        self.framebuffer_size
+    }
+
+    /// Width over height of the framebuffer, `None` while minimized.
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        let (width, height) = self.framebuffer_size;
+        (width > 0 && height > 0).then(|| width as f32 / height as f32)
+    }
+
+    /// Ratio between pixels and screen coordinates the platform asks for, e.g. 2.0 on a Retina
+    /// display. UI sizes should be multiplied by this.
+    #[allow(dead_code)]
+    pub fn content_scale(&self) -> (f32, f32) {
+        self.content_scale
+    }
+
+    /// Called by the engine when GLFW reports a new framebuffer size.
+    pub fn on_framebuffer_resized(&mut self, width: i32, height: i32) {
+        self.framebuffer_size = (width, height);
+        if width > 0 && height > 0 {
+            unsafe { gl::Viewport(0, 0, width, height) };
+        }
+    }
+
+    /// Called by the engine when the window moves to a monitor with a different scale.
+    pub fn on_content_scale_changed(&mut self, x: f32, y: f32) {
+        info!("Window content scale changed to {}x{}", x, y);
+        self.content_scale = (x, y);
+    }
+
+    #[allow(dead_code)]
+    pub fn vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Sets the swap interval; the window's context must be current.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.vsync = vsync;
+        unsafe { glfwSwapInterval(vsync as i32) };
+    }
+
+    /// Samples per pixel requested when the window was created; 0 if multisampling is off.
+    #[allow(dead_code)]
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    #[allow(dead_code)]
+    pub fn fullscreen(&self) -> FullscreenMode {
+        self.fullscreen
+    }
+
+    #[allow(dead_code)]
+    pub fn monitor(&self) -> Option<Monitor> {
+        self.monitor
+    }
+
+    /// The monitor used by later fullscreen switches.
+    #[allow(dead_code)]
+    pub fn set_monitor(&mut self, monitor: Monitor) {
+        self.monitor = Some(monitor);
+        if self.fullscreen != FullscreenMode::Windowed {
+            self.set_fullscreen(self.fullscreen, None);
+        }
+    }
+
+    /// Switches between windowed, borderless and exclusive fullscreen. Exclusive mode uses the
+    /// monitor's mode matching the windowed size (and `refresh_rate` if given), or its current
+    /// mode if there is none.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode, refresh_rate: Option<i32>) {
+        let (_, _, width, height) = self.windowed_rect;
+        let video_mode = self.monitor.and_then(|m| {
+            m.find_video_mode(width, height, refresh_rate)
+                .or_else(|| m.video_mode())
+        });
+        self.set_fullscreen_mode(mode, video_mode);
+    }
+
+    /// Like [`Window::set_fullscreen`] with an explicit video mode for exclusive fullscreen,
+    /// e.g. one picked from [`Monitor::video_modes`].
+    pub fn set_fullscreen_mode(&mut self, mode: FullscreenMode, video_mode: Option<VideoMode>) {
+        let Some(monitor) = self.monitor.or_else(Monitor::primary) else {
+            warn!("No monitor available for fullscreen");
+            return;
+        };
+        if self.fullscreen == FullscreenMode::Windowed {
+            let (mut x, mut y, mut width, mut height) = (0, 0, 0, 0);
+            unsafe {
+                glfwGetWindowPos(self.handle, &mut x, &mut y);
+                glfwGetWindowSize(self.handle, &mut width, &mut height);
+            }
+            self.windowed_rect = (x, y, width, height);
+        }
+        unsafe {
+            match mode {
+                FullscreenMode::Windowed => {
+                    let (x, y, width, height) = self.windowed_rect;
+                    glfwSetWindowAttrib(self.handle, DECORATED, TRUE);
+                    glfwSetWindowMonitor(
+                        self.handle,
+                        ptr::null_mut(),
+                        x,
+                        y,
+                        width,
+                        height,
+                        DONT_CARE,
+                    );
+                }
+                FullscreenMode::Borderless => {
+                    let Some(current) = monitor.video_mode() else {
+                        return;
+                    };
+                    let (x, y) = monitor.position();
+                    glfwSetWindowAttrib(self.handle, DECORATED, FALSE);
+                    glfwSetWindowMonitor(
+                        self.handle,
+                        ptr::null_mut(),
+                        x,
+                        y,
+                        current.width,
+                        current.height,
+                        DONT_CARE,
+                    );
+                }
+                FullscreenMode::Exclusive => {
+                    let Some(video_mode) = video_mode.or_else(|| monitor.video_mode()) else {
+                        return;
+                    };
+                    glfwSetWindowMonitor(
+                        self.handle,
+                        monitor.handle,
+                        0,
+                        0,
+                        video_mode.width,
+                        video_mode.height,
+                        video_mode.refresh_rate,
+                    );
+                }
+            }
+            // Switching monitors resets the swap interval on some platforms.
+            glfwSwapInterval(self.vsync as i32);
+        }
+        if mode != FullscreenMode::Windowed {
+            self.preferred_fullscreen = mode;
+        }
+        self.fullscreen = mode;
+        info!("Window mode: {:?} on {}", mode, monitor.name());
+    }
+
+    /// Toggles between windowed and the last used (or configured) fullscreen mode.
+    pub fn toggle_fullscreen(&mut self) {
+        match self.fullscreen {
+            FullscreenMode::Windowed => self.set_fullscreen(self.preferred_fullscreen, None),
+            _ => self.set_fullscreen(FullscreenMode::Windowed, None),
+        }
+    }
+
+    /// Loads the window icon from an image asset. Not supported on Wayland and macOS, where
+    /// GLFW ignores it.
+    pub fn set_icon(&self, path: &str) -> Result<(), String> {
+        let image = Image::load(Path::new(&get_asset_path(path)?))?;
+        let icon = GLFWimage {
+            width: image.width as i32,
+            height: image.height as i32,
+            pixels: image.pixels.as_ptr(),
+        };
+        unsafe { glfwSetWindowIcon(self.handle, 1, &icon) };
+        Ok(())
+    }
+
+    /// Uses one of the system's standard cursor shapes.
+    #[allow(dead_code)]
+    pub fn set_cursor(&mut self, shape: CursorShape) {
+        let cursor = unsafe { glfwCreateStandardCursor(shape.glfw_shape()) };
+        self.replace_cursor(cursor);
+    }
+
+    /// Uses an image asset as the cursor, with the click point `hotspot` pixels from its top left.
+    #[allow(dead_code)]
+    pub fn set_cursor_image(&mut self, path: &str, hotspot: (i32, i32)) -> Result<(), String> {
+        let image = Image::load(Path::new(&get_asset_path(path)?))?;
+        let glfw_image = GLFWimage {
+            width: image.width as i32,
+            height: image.height as i32,
+            pixels: image.pixels.as_ptr(),
+        };
+        let cursor = unsafe { glfwCreateCursor(&glfw_image, hotspot.0, hotspot.1) };
+        if cursor.is_null() {
+            return Err(format!("Failed to create cursor from {}", path));
+        }
+        self.replace_cursor(cursor);
+        Ok(())
+    }
+
+    /// Goes back to the default arrow cursor.
+    pub fn reset_cursor(&mut self) {
+        self.replace_cursor(ptr::null_mut());
+    }
+
+    fn replace_cursor(&mut self, cursor: *mut GLFWcursor) {
+        unsafe {
+            glfwSetCursor(self.handle, cursor);
+            if !self.cursor.is_null() {
+                glfwDestroyCursor(self.cursor);
+            }
+        }
+        self.cursor = cursor;
+    }
+
+    /// Destroys the window; it must not be used afterwards. Dropping does the same.
+    pub fn destroy(&mut self) {
+        if self.handle.is_null() {
+            return;
+        }
+        self.reset_cursor();
+        unsafe { glfwDestroyWindow(self.handle) };
+        self.handle = ptr::null_mut();
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        self.destroy();
+    }
+}